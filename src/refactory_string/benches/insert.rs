@@ -0,0 +1,62 @@
+//! Benchmarks of inserting content, run with `cargo bench --bench insert`.
+//!
+//! Inserted content used to be stored as a `String` per side of each chunk, which copied
+//! the content already inserted on every prepend. Median times before and after storing
+//! it in an append-only arena (same machine, same benchmarks):
+//!
+//! | Benchmark                     | `String` per side | Arena     |
+//! |-------------------------------|-------------------|-----------|
+//! | prepend_left 10k at one index | 13.74 ms          | 0.38 ms   |
+//! | append_right at every line    | 5.86 ms           | 4.72 ms   |
+//! | codemod and serialize         | 99.73 ms          | 100.94 ms |
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use refactory_string::RefactoryString;
+
+fn content() -> String {
+    "let value = compute(1, 2, 3);\n".repeat(1000)
+}
+
+fn prepend_same_index(c: &mut Criterion) {
+    let content = content();
+    c.bench_function("prepend_left 10k at one index", |b| {
+        b.iter(|| {
+            let mut s = RefactoryString::new(&content);
+            for _ in 0..10_000 {
+                s.prepend_left(black_box(300), "prefix ").unwrap();
+            }
+            s
+        })
+    });
+}
+
+fn append_every_line(c: &mut Criterion) {
+    let content = content();
+    c.bench_function("append_right at every line", |b| {
+        b.iter(|| {
+            let mut s = RefactoryString::new(&content);
+            for i in (0..content.len()).step_by(30) {
+                s.append_right(black_box(i), "// comment\n").unwrap();
+                s.prepend_left(black_box(i), "\n").unwrap();
+            }
+            s
+        })
+    });
+}
+
+fn codemod(c: &mut Criterion) {
+    let content = content();
+    c.bench_function("codemod and serialize", |b| {
+        b.iter(|| {
+            let mut s = RefactoryString::new(&content);
+            for i in (0..content.len()).step_by(30) {
+                s.overwrite(black_box(i + 4), i + 9, "renamed").unwrap();
+                s.append_left(i + 29, " // checked").unwrap();
+                s.prepend_right(i + 12, "await ").unwrap();
+            }
+            s.to_string()
+        })
+    });
+}
+
+criterion_group!(benches, prepend_same_index, append_every_line, codemod);
+criterion_main!(benches);
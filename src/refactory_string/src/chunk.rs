@@ -1,26 +1,121 @@
-use crate::error::Error;
-use alloc::borrow::ToOwned;
+use alloc::borrow::Cow;
+use alloc::collections::VecDeque;
 use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
 
 /// Chunks are parts of a memory that have an intro and an outro.
 /// They are chunks of bytes, and not strings, as we export two types; a
 /// string that deals with String content, and a buffer that deals with
 /// binary data. Because both reuse the same chunk type (this one), this type
 /// is storage agnostic.
+///
+/// Inserted and edited content is not owned by the chunk, but stored in the arena of
+/// its chunk list; the chunk only keeps ranges of that arena.
 pub(crate) struct Chunk<'a> {
-    pub left: Option<String>,
-    pub right: Option<String>,
+    pub left: Option<Inserted>,
+    pub right: Option<Inserted>,
     pub content: Option<&'a str>,
+    /// Replacement for the original content, if it was edited.
+    pub edited: Option<Range<usize>>,
+    /// Whether the source map should name the edited content after the original content.
+    pub store_name: bool,
     pub start: usize,
     pub end: usize,
 }
 
+/// Content inserted on one side of a chunk, as a list of ranges of the arena. Prepending
+/// or appending only adds a range, without copying the content already inserted.
+#[derive(Debug, Default)]
+pub(crate) struct Inserted {
+    pieces: VecDeque<Range<usize>>,
+    /// The pieces inserted by `RefactoryString::overwrite` in place of the chunk after
+    /// this one, so that resetting that chunk removes them.
+    overwrites: Vec<Range<usize>>,
+}
+
+impl Inserted {
+    pub fn is_empty(&self) -> bool {
+        self.pieces.iter().all(|r| r.start == r.end)
+    }
+
+    pub fn pieces<'s>(&'s self, arena: &'s str) -> impl Iterator<Item = &'s str> + 's {
+        self.pieces.iter().map(move |r| &arena[r.clone()])
+    }
+
+    /// Every piece, and whether it was inserted by `RefactoryString::overwrite`.
+    pub fn tagged_pieces<'s>(
+        &'s self,
+        arena: &'s str,
+    ) -> impl Iterator<Item = (&'s str, bool)> + 's {
+        self.pieces
+            .iter()
+            .map(move |r| (&arena[r.clone()], self.overwrites.contains(r)))
+    }
+
+    /// The inserted content, only allocated if it spans multiple pieces.
+    pub fn to_str<'s>(&'s self, arena: &'s str) -> Cow<'s, str> {
+        match self.pieces.len() {
+            0 => Cow::Borrowed(""),
+            1 => Cow::Borrowed(&arena[self.pieces[0].clone()]),
+            _ => Cow::Owned(self.pieces(arena).collect()),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pieces.clear();
+        self.overwrites.clear();
+    }
+
+    /// Remove the pieces inserted by `RefactoryString::overwrite`.
+    pub fn remove_overwrites(&mut self) {
+        let overwrites = core::mem::take(&mut self.overwrites);
+        self.pieces.retain(|r| !overwrites.contains(r));
+    }
+
+    /// Remove every piece but the ones inserted by `RefactoryString::overwrite`.
+    pub fn retain_overwrites(&mut self) {
+        let overwrites = &self.overwrites;
+        self.pieces.retain(|r| overwrites.contains(r));
+    }
+
+    /// The number of bytes inserted.
+    pub fn len(&self) -> usize {
+        self.pieces.iter().map(|r| r.end - r.start).sum()
+    }
+
+    /// Copy the pieces from one arena to another, and reference them there instead.
+    pub fn relocate(&mut self, from: &str, to: &mut Arena) {
+        for range in self.pieces.iter_mut() {
+            let relocated = to.push(&from[range.clone()]);
+            if let Some(overwrite) = self.overwrites.iter_mut().find(|r| **r == *range) {
+                *overwrite = relocated.clone();
+            }
+            *range = relocated;
+        }
+    }
+
+    /// Replace every piece by a single range. It is only considered inserted by
+    /// `RefactoryString::overwrite` if all the pieces were.
+    pub fn replace(&mut self, range: Range<usize>) {
+        let overwrite = self.pieces.iter().all(|r| self.overwrites.contains(r));
+        self.pieces.clear();
+        self.overwrites.clear();
+        if overwrite {
+            self.overwrites.push(range.clone());
+        }
+        self.pieces.push_back(range);
+    }
+}
+
 impl<'a> Chunk<'a> {
     pub fn new(original_content: &'a str) -> Chunk<'a> {
         Chunk {
-            left: Some(String::new()),
-            content: Some(&original_content[..]),
-            right: Some(String::new()),
+            left: Some(Inserted::default()),
+            content: Some(original_content),
+            edited: None,
+            store_name: false,
+            right: Some(Inserted::default()),
             start: 0,
             end: original_content.len(),
         }
@@ -30,49 +125,74 @@ impl<'a> Chunk<'a> {
         self.end - self.start
     }
 
-    pub fn to_string(&self) -> String {
-        let mut s = String::new();
-        if let Some(ref l) = self.left {
-            s.push_str(l);
-        }
-        if let Some(ref c) = self.content {
-            s.push_str(c);
-        }
-        if let Some(ref r) = self.right {
-            s.push_str(r);
+    pub fn append_right(&mut self, range: Range<usize>) {
+        if let Some(ref mut r) = self.right {
+            r.pieces.push_back(range);
         }
-        s
     }
 
-    pub fn append_right(&mut self, content: &str) -> Result<(), Error> {
+    /// Append content inserted by `RefactoryString::overwrite` in place of the next chunk.
+    pub fn append_overwrite(&mut self, range: Range<usize>) {
         if let Some(ref mut r) = self.right {
-            r.push_str(content);
+            r.pieces.push_back(range.clone());
+            r.overwrites.push(range);
         }
-        Ok(())
     }
 
-    pub fn append_left(&mut self, content: &str) -> Result<(), Error> {
+    pub fn append_left(&mut self, range: Range<usize>) {
         if let Some(ref mut l) = self.left {
-            l.push_str(content);
+            l.pieces.push_back(range);
         }
-        Ok(())
     }
 
-    pub fn prepend_right(&mut self, content: &str) -> Result<(), Error> {
+    pub fn prepend_right(&mut self, range: Range<usize>) {
         if let Some(ref mut r) = self.right {
-            let mut tmp = content.to_owned();
-            tmp.push_str(r);
-            self.right = Some(tmp);
+            r.pieces.push_front(range);
         }
-        Ok(())
     }
 
-    pub fn prepend_left(&mut self, content: &str) -> Result<(), Error> {
+    pub fn prepend_left(&mut self, range: Range<usize>) {
         if let Some(ref mut l) = self.left {
-            let mut tmp = content.to_owned();
-            tmp.push_str(l);
-            self.left = Some(tmp);
+            l.pieces.push_front(range);
         }
-        Ok(())
+    }
+
+    /// The edited content, if the original content was edited.
+    pub fn edited<'s>(&'s self, arena: &'s str) -> Option<&'s str> {
+        self.edited.as_ref().map(|r| &arena[r.clone()])
+    }
+
+    /// Every piece of the output of this chunk, in order.
+    pub fn pieces<'s>(&'s self, arena: &'s str) -> impl Iterator<Item = &'s str> + 's {
+        let content = match self.edited(arena) {
+            Some(edited) => Some(edited),
+            None => self.content,
+        };
+        let left = self.left.iter().flat_map(move |l| l.pieces(arena));
+        let right = self.right.iter().flat_map(move |r| r.pieces(arena));
+        left.chain(content).chain(right)
+    }
+}
+
+/// Append-only storage for the content inserted in a chunk list.
+#[derive(Debug, Default)]
+pub(crate) struct Arena {
+    content: String,
+}
+
+impl Arena {
+    /// Store a content and return its range.
+    pub fn push(&mut self, content: &str) -> Range<usize> {
+        let start = self.content.len();
+        self.content.push_str(content);
+        start..self.content.len()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.content
+    }
+
+    pub fn len(&self) -> usize {
+        self.content.len()
     }
 }
@@ -1,10 +1,31 @@
-use crate::chunk::Chunk;
+use crate::chunk::{Arena, Chunk, Inserted};
 use crate::error::Error;
+use crate::line_ending::LineEnding;
+use alloc::borrow::Cow;
 use alloc::boxed::Box;
 use alloc::string::String;
 
+/// Statistics about the chunks of a RefactoryString, useful to monitor their growth.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ChunkStats {
+    /// The number of chunks.
+    pub chunks: usize,
+
+    /// The number of chunks without any change.
+    pub pristine: usize,
+
+    /// The number of bytes of inserted and edited content.
+    pub inserted_bytes: usize,
+
+    /// The number of bytes used to store inserted and edited content, including content
+    /// that was replaced or removed since.
+    pub arena_bytes: usize,
+}
+
 pub(crate) struct ChunkList<'a> {
+    original: &'a str,
     head: Link<'a>,
+    arena: Arena,
 }
 
 type Link<'a> = Option<Box<Node<'a>>>;
@@ -16,11 +37,45 @@ struct Node<'a> {
 impl<'a> ChunkList<'a> {
     pub fn new(original_content: &'a str) -> Self {
         ChunkList {
+            original: original_content,
             head: Some(Box::new(Node {
                 elem: Chunk::new(original_content),
                 next: None,
             })),
+            arena: Arena::default(),
+        }
+    }
+
+    /// The storage of the inserted and edited content of the chunks.
+    pub fn arena(&self) -> &str {
+        self.arena.as_str()
+    }
+
+    /// Store a content in the arena, to be referenced by a chunk.
+    #[cfg(test)]
+    pub fn alloc(&mut self, content: &str) -> core::ops::Range<usize> {
+        self.arena.push(content)
+    }
+
+    /// Insert content at an index. Content inserted to the left of the index is added to
+    /// the right side of the chunk before it, and vice versa.
+    pub fn insert(
+        &mut self,
+        index: usize,
+        content: &str,
+        left: bool,
+        append: bool,
+    ) -> Result<(), Error> {
+        let range = self.arena.push(content);
+        let (l, r) = self.split(index)?;
+
+        match (left, append) {
+            (true, true) => l.append_right(range),
+            (false, true) => r.append_left(range),
+            (true, false) => l.prepend_right(range),
+            (false, false) => r.prepend_left(range),
         }
+        Ok(())
     }
 
     pub fn split(&mut self, index: usize) -> Result<(&mut Chunk<'a>, &mut Chunk<'a>), Error> {
@@ -30,21 +85,42 @@ impl<'a> ChunkList<'a> {
         if let Some(node) = curr {
             let chunk = &mut node.elem;
 
-            if index == chunk.start && prev.is_some() {
-                return Ok((&mut prev.unwrap().elem, chunk));
+            if index == chunk.start {
+                if let Some(prev) = prev {
+                    return Ok((&mut prev.elem, chunk));
+                }
             }
             if index == chunk.end && node.next.is_some() {
-                return Ok((chunk, &mut node.next.as_mut().unwrap().as_mut().elem));
+                let next = node
+                    .next
+                    .as_deref_mut()
+                    .ok_or(Error::InvalidInternalState)?;
+                return Ok((chunk, &mut next.elem));
+            }
+
+            if chunk.edited.is_some() && index > chunk.start && index < chunk.end {
+                return Err(Error::CannotSplitEdit {
+                    index,
+                    start: chunk.start,
+                    end: chunk.end,
+                });
             }
 
             let inner_start = index - chunk.start;
-            let orig_right = chunk.right.as_ref().map(|_| String::new());
+            let orig_right = chunk.right.as_ref().map(|_| Inserted::default());
             let right = chunk.right.take();
             let new_chunk = Chunk {
                 // Left is None if Chunk does not have a right.
-                left: right.as_ref().map(|_| String::new()),
+                left: right.as_ref().map(|_| Inserted::default()),
                 right,
                 content: chunk.content.as_ref().map(|c| &c[inner_start..]),
+                // The edit follows the original content it replaces.
+                edited: if inner_start == 0 {
+                    chunk.edited.take()
+                } else {
+                    None
+                },
+                store_name: inner_start == 0 && core::mem::take(&mut chunk.store_name),
                 start: index,
                 end: chunk.end,
             };
@@ -60,7 +136,10 @@ impl<'a> ChunkList<'a> {
 
             Ok((chunk, &mut node.next.as_mut().unwrap().as_mut().elem))
         } else {
-            Err(Error::IndexOutOfBoundError(index))
+            Err(Error::IndexOutOfBound {
+                index,
+                length: prev.map_or(0, |node| node.elem.end),
+            })
         }
     }
 
@@ -71,10 +150,11 @@ impl<'a> ChunkList<'a> {
         let _ = self.split(start)?;
         let _ = self.split(end)?;
 
-        let mut it = self.iter_mut();
-        while let Some(c) = it.next() {
+        for c in self.iter_mut() {
             if c.start >= start && start < c.end {
                 c.content = None;
+                c.edited = None;
+                c.store_name = false;
                 c.left = None;
                 c.right = None;
             }
@@ -86,6 +166,202 @@ impl<'a> ChunkList<'a> {
         Ok(())
     }
 
+    /// Remove the content between two indices, and insert content to the left of them in
+    /// its place. The content is marked so that resetting the range removes it as well.
+    pub fn overwrite(&mut self, start: usize, end: usize, content: &str) -> Result<(), Error> {
+        self.remove(start, end)?;
+        let range = self.arena.push(content);
+        let (l, _) = self.split(start)?;
+
+        if start < end {
+            l.append_overwrite(range);
+        } else {
+            l.append_right(range);
+        }
+        Ok(())
+    }
+
+    /// Replace the original content between two indices. The first chunk of the range
+    /// receives the new content while the others are emptied. Content inserted inside the
+    /// range is cleared, and content inserted at its boundaries is kept if `content_only`.
+    pub fn edit(
+        &mut self,
+        start: usize,
+        end: usize,
+        content: &str,
+        content_only: bool,
+        store_name: bool,
+    ) -> Result<(), Error> {
+        if start >= end {
+            return Ok(());
+        }
+        let _ = self.split(start)?;
+        let _ = self.split(end)?;
+        let range = self.arena.push(content);
+
+        let mut first = true;
+        for c in self.iter_mut() {
+            if c.start >= start && c.end <= end && c.start < c.end {
+                c.edited = Some(if first {
+                    range.clone()
+                } else {
+                    range.end..range.end
+                });
+                c.store_name = first && store_name;
+                first = false;
+
+                if let Some(ref mut left) = c.left {
+                    if !content_only || c.start != start {
+                        left.clear();
+                    }
+                }
+                if let Some(ref mut right) = c.right {
+                    if !content_only || c.end != end {
+                        right.clear();
+                    }
+                }
+            }
+            if c.end >= end {
+                break; // No need to continue.
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Restore the original content between two indices, removing every edit and the
+    /// content inserted to the chunks of the range, or in place of them by `overwrite`.
+    /// Chunks left untouched are merged.
+    pub fn reset(&mut self, start: usize, end: usize) -> Result<(), Error> {
+        let _ = self.split(start)?;
+        let _ = self.split(end)?;
+        let original = self.original;
+
+        for c in self.iter_mut() {
+            if c.start >= start && c.end <= end {
+                c.content = Some(&original[c.start..c.end]);
+                c.edited = None;
+                c.store_name = false;
+                c.left = Some(Inserted::default());
+
+                // The content overwriting the original content after the range is kept.
+                let mut right = c.right.take().unwrap_or_default();
+                if c.end == end {
+                    right.retain_overwrites();
+                } else {
+                    right.clear();
+                }
+                c.right = Some(right);
+            } else if c.end == start && start < end {
+                if let Some(ref mut right) = c.right {
+                    right.remove_overwrites();
+                }
+            }
+            if c.end >= end {
+                break; // No need to continue.
+            }
+        }
+
+        self.merge(start, end);
+        Ok(())
+    }
+
+    /// Merge every untouched chunk with its neighbours, and drop the content of the arena
+    /// that is not referenced anymore.
+    pub fn compact(&mut self) {
+        self.merge(0, self.original.len());
+
+        let mut arena = Arena::default();
+        let from = self.arena.as_str();
+        let mut next = self.head.as_deref_mut();
+        while let Some(node) = next {
+            let chunk = &mut node.elem;
+            for inserted in chunk.left.iter_mut().chain(chunk.right.iter_mut()) {
+                inserted.relocate(from, &mut arena);
+            }
+            if let Some(ref mut edited) = chunk.edited {
+                *edited = arena.push(&from[edited.clone()]);
+            }
+            next = node.next.as_deref_mut();
+        }
+        self.arena = arena;
+    }
+
+    pub fn stats(&self) -> ChunkStats {
+        let mut stats = ChunkStats {
+            arena_bytes: self.arena.len(),
+            ..ChunkStats::default()
+        };
+
+        for chunk in self.iter() {
+            let inserted = chunk.left.iter().chain(chunk.right.iter());
+            let inserted = inserted.map(Inserted::len).sum::<usize>()
+                + chunk.edited.as_ref().map_or(0, |r| r.end - r.start);
+
+            stats.chunks += 1;
+            stats.inserted_bytes += inserted;
+            if inserted == 0 && chunk.content.is_some() && chunk.edited.is_none() {
+                stats.pristine += 1;
+            }
+        }
+        stats
+    }
+
+    /// Merge the consecutive chunks that meet between two indices, when no change is lost.
+    fn merge(&mut self, start: usize, end: usize) {
+        let original = self.original;
+        let mut current = self.head.as_deref_mut();
+
+        while let Some(node) = current {
+            if node.elem.end > end {
+                break;
+            }
+            let mergeable = match node.next {
+                Some(ref next) => node.elem.end >= start && is_mergeable(&node.elem, &next.elem),
+                None => false,
+            };
+
+            if mergeable {
+                if let Some(next) = node.next.take() {
+                    let Node { elem, next } = *next;
+                    node.elem.end = elem.end;
+                    if node.elem.content.is_some() {
+                        node.elem.content = Some(&original[node.elem.start..elem.end]);
+                    }
+                    node.elem.right = elem.right;
+                    node.next = next;
+                }
+                current = Some(node);
+            } else {
+                current = node.next.as_deref_mut();
+            }
+        }
+    }
+
+    /// Convert the line endings of all inserted and edited content to the given style.
+    pub fn normalize_inserted(&mut self, ending: LineEnding) {
+        let arena = &mut self.arena;
+        let mut next = self.head.as_deref_mut();
+
+        while let Some(node) = next {
+            let chunk = &mut node.elem;
+            let inserted = chunk.left.iter_mut().chain(chunk.right.iter_mut());
+            for inserted in inserted {
+                let normalized = owned(ending.normalize(&inserted.to_str(arena.as_str())));
+                if let Some(normalized) = normalized {
+                    inserted.replace(arena.push(&normalized));
+                }
+            }
+            if let Some(ref mut edited) = chunk.edited {
+                let normalized = owned(ending.normalize(&arena.as_str()[edited.clone()]));
+                if let Some(normalized) = normalized {
+                    *edited = arena.push(&normalized);
+                }
+            }
+            next = node.next.as_deref_mut();
+        }
+    }
+
     fn get_node_at(&mut self, index: usize) -> (Option<&mut Node<'a>>, Option<&mut Node<'a>>) {
         let mut current = &mut self.head;
         let mut previous: Option<&mut Node<'a>> = None;
@@ -122,15 +398,15 @@ impl<'a> ChunkList<'a> {
         self.get_node_at(index).1.map(|node| &mut node.elem)
     }
 
-    pub fn iter<'b: 'a>(&'b self) -> Iter<'_, 'a> {
+    pub fn iter(&self) -> Iter<'_, 'a> {
         Iter {
-            next: self.head.as_ref().map(|node| &**node),
+            next: self.head.as_deref(),
         }
     }
 
     pub fn iter_mut(&mut self) -> IterMut<'_, 'a> {
         IterMut {
-            next: self.head.as_mut().map(|node| &mut **node),
+            next: self.head.as_deref_mut(),
         }
     }
 }
@@ -144,8 +420,28 @@ impl<'a> Drop for ChunkList<'a> {
     }
 }
 
+/// Whether two consecutive chunks can be merged without losing any change; either both
+/// are untouched, or both are removed.
+fn is_mergeable(a: &Chunk<'_>, b: &Chunk<'_>) -> bool {
+    let is_pristine = |c: &Chunk<'_>| c.content.is_some() && c.edited.is_none();
+    let is_removed = |c: &Chunk<'_>| c.content.is_none() && c.edited.is_none();
+
+    (is_pristine(a)
+        && is_pristine(b)
+        && a.right.as_ref().is_some_and(Inserted::is_empty)
+        && b.left.as_ref().is_some_and(Inserted::is_empty))
+        || (is_removed(a) && is_removed(b))
+}
+
+fn owned(content: Cow<'_, str>) -> Option<String> {
+    match content {
+        Cow::Owned(content) => Some(content),
+        Cow::Borrowed(_) => None,
+    }
+}
+
 pub(crate) struct Iter<'b, 'a: 'b> {
-    next: Option<&'a Node<'b>>,
+    next: Option<&'b Node<'a>>,
 }
 
 impl<'b, 'a: 'b> Iterator for Iter<'b, 'a> {
@@ -153,7 +449,7 @@ impl<'b, 'a: 'b> Iterator for Iter<'b, 'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next.map(|node| {
-            self.next = node.next.as_ref().map(|node| &**node);
+            self.next = node.next.as_deref();
             &node.elem
         })
     }
@@ -168,7 +464,7 @@ impl<'b, 'a: 'b> Iterator for IterMut<'b, 'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next.take().map(|node| {
-            self.next = node.next.as_mut().map(|node| &mut **node);
+            self.next = node.next.as_deref_mut();
             &mut node.elem
         })
     }
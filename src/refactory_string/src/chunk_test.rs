@@ -2,24 +2,32 @@
 use crate::chunk::Chunk;
 use crate::chunk_list::ChunkList;
 use crate::error::Error;
-use alloc::string::ToString;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+fn text(cl: &ChunkList, c: &Chunk) -> String {
+    c.pieces(cl.arena()).collect()
+}
+
+fn texts(cl: &ChunkList) -> Vec<String> {
+    cl.iter().map(|c| text(cl, c)).collect()
+}
 
 #[test]
 fn basic() -> Result<(), Error> {
     let content = "1234";
-    let mut cl = ChunkList::new(&content);
+    let mut cl = ChunkList::new(content);
 
     if let Some(c) = cl.get_chunk_at(0) {
         assert_eq!(c.start, 0);
         assert_eq!(c.end, 4);
-        assert_eq!(&c.to_string(), content);
+        assert_eq!(&text(&cl, c), content);
     } else {
-        assert!(false);
+        panic!("Expected a chunk at index 0.");
     }
 
-    let (c1, c2) = cl.split(2)?;
-    assert_eq!(&c1.to_string(), &content[..2]);
-    assert_eq!(&c2.to_string(), &content[2..]);
+    let _ = cl.split(2)?;
+    assert_eq!(texts(&cl), [&content[..2], &content[2..]]);
 
     // Verify that slicing twice at the same index returns the same chunks.
     unsafe {
@@ -53,16 +61,14 @@ fn basic() -> Result<(), Error> {
 fn append() -> Result<(), Error> {
     let mut cl = ChunkList::new("1234");
 
-    if let Some(ref mut c) = cl.get_mut_chunk_at(0).take() {
-        c.append_right("5678")?;
-        assert_eq!(&c.to_string(), "12345678");
+    let range = cl.alloc("5678");
+    if let Some(ref mut c) = cl.get_mut_chunk_at(0) {
+        c.append_right(range);
     }
+    assert_eq!(texts(&cl), ["12345678"]);
 
     let _ = cl.split(2)?;
-    let mut it = cl.iter();
-    assert_eq!(it.next().map(|x| x.to_string()), Some("12".to_string()));
-    assert_eq!(it.next().map(|x| x.to_string()), Some("345678".to_string()));
-    assert_eq!(it.next().map(|x| x.to_string()), None);
+    assert_eq!(texts(&cl), ["12", "345678"]);
 
     Ok(())
 }
@@ -71,16 +77,14 @@ fn append() -> Result<(), Error> {
 fn prepend() -> Result<(), Error> {
     let mut cl = ChunkList::new("1234");
 
-    if let Some(ref mut c) = cl.get_mut_chunk_at(0).take() {
-        c.prepend_left("5678")?;
-        assert_eq!(&c.to_string(), "56781234");
+    let range = cl.alloc("5678");
+    if let Some(ref mut c) = cl.get_mut_chunk_at(0) {
+        c.prepend_left(range);
     }
+    assert_eq!(texts(&cl), ["56781234"]);
 
     let _ = cl.split(2)?;
-    let mut it = cl.iter();
-    assert_eq!(it.next().map(|x| x.to_string()), Some("567812".to_string()));
-    assert_eq!(it.next().map(|x| x.to_string()), Some("34".to_string()));
-    assert_eq!(it.next().map(|x| x.to_string()), None);
+    assert_eq!(texts(&cl), ["567812", "34"]);
 
     Ok(())
 }
@@ -89,19 +93,23 @@ fn prepend() -> Result<(), Error> {
 fn append_prepend() -> Result<(), Error> {
     let mut cl = ChunkList::new("23");
 
+    let ranges: Vec<_> = ["1", "0", "8", "9", "4", "5", "6", "7"]
+        .iter()
+        .map(|c| cl.alloc(c))
+        .collect();
     let chunk = cl.get_mut_chunk_at(0).ok_or(Error::InvalidInternalState)?;
-    chunk.append_left("1")?;
-    chunk.prepend_left("0")?;
-    chunk.append_left("8")?;
-    chunk.prepend_left("9")?;
-
-    assert_eq!(&chunk.to_string(), "901823");
+    chunk.append_left(ranges[0].clone());
+    chunk.prepend_left(ranges[1].clone());
+    chunk.append_left(ranges[2].clone());
+    chunk.prepend_left(ranges[3].clone());
+    assert_eq!(texts(&cl), ["901823"]);
 
-    chunk.append_right("4")?;
-    chunk.prepend_right("5")?;
-    chunk.append_right("6")?;
-    chunk.prepend_right("7")?;
-    assert_eq!(&chunk.to_string(), "9018237546");
+    let chunk = cl.get_mut_chunk_at(0).ok_or(Error::InvalidInternalState)?;
+    chunk.append_right(ranges[4].clone());
+    chunk.prepend_right(ranges[5].clone());
+    chunk.append_right(ranges[6].clone());
+    chunk.prepend_right(ranges[7].clone());
+    assert_eq!(texts(&cl), ["9018237546"]);
 
     Ok(())
 }
@@ -137,11 +145,38 @@ fn get_chunk_at() -> Result<(), Error> {
     let mut cl = ChunkList::new("1234");
 
     let _ = cl.split(2)?;
-    assert_eq!(cl.get_chunk_at(0).map(|x| x.to_string()), Some("12".to_string()));
-    assert_eq!(cl.get_chunk_at(1).map(|x| x.to_string()), Some("12".to_string()));
-    assert_eq!(cl.get_chunk_at(2).map(|x| x.to_string()), Some("34".to_string()));
-    assert_eq!(cl.get_chunk_at(3).map(|x| x.to_string()), Some("34".to_string()));
-    assert_eq!(cl.get_chunk_at(4).map(|x| x.to_string()), None);
+    assert_eq!(
+        cl.get_chunk_at(0).map(|x| text(&cl, x)),
+        Some("12".to_string())
+    );
+    assert_eq!(
+        cl.get_chunk_at(1).map(|x| text(&cl, x)),
+        Some("12".to_string())
+    );
+    assert_eq!(
+        cl.get_chunk_at(2).map(|x| text(&cl, x)),
+        Some("34".to_string())
+    );
+    assert_eq!(
+        cl.get_chunk_at(3).map(|x| text(&cl, x)),
+        Some("34".to_string())
+    );
+    assert_eq!(cl.get_chunk_at(4).map(|x| text(&cl, x)), None);
 
     Ok(())
 }
+
+#[test]
+fn reset() -> Result<(), Error> {
+    let mut cl = ChunkList::new("0123456789");
+    cl.insert(2, "a", false, true)?;
+    cl.edit(4, 5, "b", false, false)?;
+    cl.remove(7, 8)?;
+    assert_eq!(texts(&cl), ["01", "a23", "b", "56", "", "89"]);
+
+    cl.reset(3, 6)?;
+    assert_eq!(texts(&cl), ["01", "a23456", "", "89"]);
+    cl.reset(0, 10)?;
+    assert_eq!(texts(&cl), ["0123456789"]);
+    Ok(())
+}
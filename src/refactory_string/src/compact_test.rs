@@ -0,0 +1,49 @@
+#![cfg(test)]
+use crate::error::Error;
+use crate::{ChunkStats, LineEnding, RefactoryString};
+use alloc::string::ToString;
+
+#[test]
+fn compact() -> Result<(), Error> {
+    let content = "0123456789".repeat(10);
+    let mut s = RefactoryString::new(&content);
+    for i in 0..100 {
+        s.append_left(i, "")?;
+    }
+    s.append_left(50, "x")?;
+    s.overwrite(70, 80, "y")?;
+    assert_eq!(s.chunk_stats().chunks, 101);
+
+    s.compact();
+    assert_eq!(
+        s.chunk_stats(),
+        ChunkStats {
+            chunks: 4,
+            pristine: 1,
+            inserted_bytes: 2,
+            arena_bytes: 2
+        }
+    );
+    assert_eq!(
+        s.to_string(),
+        content[..50].to_string() + "x" + &content[50..70] + "y" + &content[80..]
+    );
+    Ok(())
+}
+
+#[test]
+fn compact_arena() -> Result<(), Error> {
+    let mut s = RefactoryString::new("a\nb");
+    s.append_left(1, "\r\n")?;
+    s.convert_line_endings(LineEnding::Lf)?;
+    assert_eq!(&s.to_string(), "a\n\nb");
+
+    let stats = s.chunk_stats();
+    assert_eq!((stats.inserted_bytes, stats.arena_bytes), (1, 3));
+
+    s.compact();
+    let stats = s.chunk_stats();
+    assert_eq!((stats.inserted_bytes, stats.arena_bytes), (1, 1));
+    assert_eq!(&s.to_string(), "a\n\nb");
+    Ok(())
+}
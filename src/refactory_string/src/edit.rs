@@ -0,0 +1,33 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A single change to the original content: the original range `start..end` is replaced
+/// by `content`. Insertions have `start == end`, and removals have an empty `content`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub content: String,
+}
+
+impl Edit {
+    pub fn is_insertion(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Push an edit at the end of a sorted list, merging it with the last edit if they touch.
+pub(crate) fn push_edit(edits: &mut Vec<Edit>, start: usize, end: usize, content: &str) {
+    if let Some(last) = edits.last_mut() {
+        if last.end == start {
+            last.end = end;
+            last.content.push_str(content);
+            return;
+        }
+    }
+    edits.push(Edit {
+        start,
+        end,
+        content: String::from(content),
+    });
+}
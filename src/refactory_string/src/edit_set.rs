@@ -0,0 +1,128 @@
+use crate::error::Error;
+use crate::RefactoryString;
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::sync::{Mutex, PoisonError};
+
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Operation {
+    AppendLeft(usize, String),
+    PrependLeft(usize, String),
+    AppendRight(usize, String),
+    PrependRight(usize, String),
+    Overwrite(usize, usize, String),
+    Remove(usize, usize),
+}
+
+/// A collection of edits recorded from multiple threads, to be applied later to a
+/// RefactoryString. `EditSet` is `Send + Sync`, and can be shared by reference.
+///
+/// Edits are recorded through an [`EditScope`], created with a key (for example the index
+/// of the rule recording them). Scopes are applied sorted by key, and the edits of a scope
+/// in the order they were recorded, so the result does not depend on which thread
+/// finished first. Scopes sharing a key are ordered by their edits.
+#[derive(Debug, Default)]
+pub struct EditSet {
+    scopes: Mutex<Vec<(u64, Vec<Operation>)>>,
+}
+
+impl EditSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a scope to record edits into. The edits are added to the set when the scope
+    /// is dropped.
+    pub fn scope(&self, key: u64) -> EditScope<'_> {
+        EditScope {
+            set: self,
+            key,
+            operations: Vec::new(),
+        }
+    }
+
+    /// The number of edits recorded.
+    pub fn len(&self) -> usize {
+        self.lock().iter().map(|(_, ops)| ops.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Apply all edits to a RefactoryString. On error, the edits applied before the
+    /// failing one are kept.
+    pub fn apply(self, s: &mut RefactoryString<'_>) -> Result<(), Error> {
+        let mut scopes = self
+            .scopes
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        scopes.sort();
+
+        for operation in scopes.iter().flat_map(|(_, ops)| ops) {
+            match operation {
+                Operation::AppendLeft(index, content) => s.append_left(*index, content),
+                Operation::PrependLeft(index, content) => s.prepend_left(*index, content),
+                Operation::AppendRight(index, content) => s.append_right(*index, content),
+                Operation::PrependRight(index, content) => s.prepend_right(*index, content),
+                Operation::Overwrite(start, end, content) => s.overwrite(*start, *end, content),
+                Operation::Remove(start, end) => s.remove(*start, *end),
+            }?;
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<(u64, Vec<Operation>)>> {
+        // Recording cannot leave the list in an inconsistent state, so poisoning is ignored.
+        self.scopes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Records edits for an [`EditSet`]. Edits are buffered locally and only added to the
+/// set when the scope is dropped. Indices are validated when the set is applied.
+#[derive(Debug)]
+pub struct EditScope<'s> {
+    set: &'s EditSet,
+    key: u64,
+    operations: Vec<Operation>,
+}
+
+impl EditScope<'_> {
+    pub fn append_left(&mut self, index: usize, content: &str) {
+        self.operations
+            .push(Operation::AppendLeft(index, content.into()));
+    }
+
+    pub fn prepend_left(&mut self, index: usize, content: &str) {
+        self.operations
+            .push(Operation::PrependLeft(index, content.into()));
+    }
+
+    pub fn append_right(&mut self, index: usize, content: &str) {
+        self.operations
+            .push(Operation::AppendRight(index, content.into()));
+    }
+
+    pub fn prepend_right(&mut self, index: usize, content: &str) {
+        self.operations
+            .push(Operation::PrependRight(index, content.into()));
+    }
+
+    pub fn overwrite(&mut self, start: usize, end: usize, content: &str) {
+        self.operations
+            .push(Operation::Overwrite(start, end, content.into()));
+    }
+
+    pub fn remove(&mut self, start: usize, end: usize) {
+        self.operations.push(Operation::Remove(start, end));
+    }
+}
+
+impl Drop for EditScope<'_> {
+    fn drop(&mut self) {
+        if !self.operations.is_empty() {
+            let operations = core::mem::take(&mut self.operations);
+            self.set.lock().push((self.key, operations));
+        }
+    }
+}
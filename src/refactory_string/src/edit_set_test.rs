@@ -0,0 +1,68 @@
+#![cfg(all(test, feature = "std"))]
+use crate::error::Error;
+use crate::{EditScope, EditSet, Merge, RefactoryString, SourceMap};
+use alloc::string::{String, ToString};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn send_sync() {
+    assert_send_sync::<RefactoryString<'static>>();
+    assert_send_sync::<SourceMap>();
+    assert_send_sync::<Merge<'static>>();
+    assert_send_sync::<EditSet>();
+    assert_send_sync::<EditScope<'static>>();
+}
+
+fn apply_from_threads(content: &str, order: &[u64]) -> Result<String, Error> {
+    let set = EditSet::new();
+    std::thread::scope(|threads| {
+        for &key in order {
+            let set = &set;
+            threads.spawn(move || {
+                let mut scope = set.scope(key);
+                match key {
+                    0 => {
+                        scope.overwrite(0, 3, "let");
+                        scope.append_left(9, " + 1");
+                    }
+                    1 => scope.append_left(9, " * 2"),
+                    _ => scope.append_right(10, " // fixed"),
+                }
+            });
+        }
+    });
+    assert_eq!(set.len(), 4);
+
+    let mut s = RefactoryString::new(content);
+    set.apply(&mut s)?;
+    Ok(s.to_string())
+}
+
+#[test]
+fn deterministic_order() -> Result<(), Error> {
+    let content = "var x = 1;";
+    let expected = "let x = 1 + 1 * 2; // fixed";
+    let orders: [&[u64]; 3] = [&[0, 1, 2], &[2, 1, 0], &[1, 2, 0]];
+    for order in orders.iter() {
+        assert_eq!(apply_from_threads(content, order)?, expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn invalid_edit() {
+    let set = EditSet::new();
+    set.scope(0).remove(2, 1);
+    assert!(!set.is_empty());
+
+    let mut s = RefactoryString::new("abc");
+    assert_eq!(
+        set.apply(&mut s),
+        Err(Error::InvalidRange {
+            start: 2,
+            end: 1,
+            length: 3
+        })
+    );
+}
@@ -1,8 +1,89 @@
-#[derive(Debug)]
+use core::fmt;
+
+/// Errors returned by RefactoryString operations. Indices always refer to the original
+/// content, and `length` is the length of the original content.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Error {
-    IndexOutOfBoundError(usize),
-    EssentialContentCannotBeAppended,
-    EssentialContentCannotBePrepended,
-    ContentShouldNotBeRemoved,
+    /// An index is past the end of the original content.
+    IndexOutOfBound { index: usize, length: usize },
+
+    /// An index falls inside a multi-byte UTF-8 character of the original content.
+    NotACharBoundary { index: usize },
+
+    /// A range ends before it starts, or ends past the end of the original content.
+    InvalidRange {
+        start: usize,
+        end: usize,
+        length: usize,
+    },
+
+    /// An index falls strictly inside a range `start..end` whose content was already
+    /// replaced, so it cannot be split.
+    CannotSplitEdit {
+        index: usize,
+        start: usize,
+        end: usize,
+    },
+
+    /// Two edits of the same batch overlap. `start..end` is the range of the edit that
+    /// overlaps one before it.
+    OverlappingEdits { start: usize, end: usize },
+
+    /// Two RefactoryStrings that were expected to share the same original content do not.
+    MismatchedOriginals,
+
+    /// The internal chunk list is in an unexpected state. This is a bug.
     InvalidInternalState,
+
+    /// The `mappings` of a source map could not be decoded. `position` is the byte
+    /// offset of the faulty segment or character in the mappings string.
+    InvalidMappings { position: usize },
+
+    /// A source map segment references a source or name that does not exist.
+    SourceMapIndexOutOfBound { index: usize, length: usize },
 }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::IndexOutOfBound { index, length } => write!(
+                f,
+                "index {} is out of bound of the original content (length {})",
+                index, length
+            ),
+            Error::NotACharBoundary { index } => write!(
+                f,
+                "index {} is not on a character boundary of the original content",
+                index
+            ),
+            Error::InvalidRange { start, end, length } => write!(
+                f,
+                "range {}..{} is invalid for the original content (length {})",
+                start, end, length
+            ),
+            Error::CannotSplitEdit { index, start, end } => write!(
+                f,
+                "index {} is inside the range {}..{}, which was already edited",
+                index, start, end
+            ),
+            Error::OverlappingEdits { start, end } => {
+                write!(f, "the edit of {}..{} overlaps another edit", start, end)
+            }
+            Error::MismatchedOriginals => {
+                f.write_str("the original contents of both sides are different")
+            }
+            Error::InvalidInternalState => f.write_str("invalid internal state"),
+            Error::InvalidMappings { position } => {
+                write!(f, "invalid source map mappings at position {}", position)
+            }
+            Error::SourceMapIndexOutOfBound { index, length } => write!(
+                f,
+                "source map index {} is out of bound (length {})",
+                index, length
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
@@ -0,0 +1,56 @@
+#![cfg(test)]
+use crate::{Error, RefactoryString};
+use alloc::string::ToString;
+
+#[test]
+fn index_out_of_bound() {
+    let mut s = RefactoryString::new("abc");
+
+    assert_eq!(
+        s.append_left(4, "x"),
+        Err(Error::IndexOutOfBound {
+            index: 4,
+            length: 3
+        })
+    );
+    assert_eq!(
+        s.append_left(4, "x").unwrap_err().to_string(),
+        "index 4 is out of bound of the original content (length 3)"
+    );
+    assert!(s.append_left(3, "x").is_ok());
+}
+
+#[test]
+fn invalid_range() {
+    let mut s = RefactoryString::new("abc");
+
+    assert_eq!(
+        s.remove(2, 1),
+        Err(Error::InvalidRange {
+            start: 2,
+            end: 1,
+            length: 3
+        })
+    );
+    assert_eq!(
+        s.overwrite(1, 5, "x"),
+        Err(Error::InvalidRange {
+            start: 1,
+            end: 5,
+            length: 3
+        })
+    );
+    assert_eq!(&s.to_string(), "abc");
+}
+
+#[test]
+fn not_a_char_boundary() {
+    let mut s = RefactoryString::new("héllo");
+
+    assert_eq!(
+        s.append_right(2, "x"),
+        Err(Error::NotACharBoundary { index: 2 })
+    );
+    assert!(s.append_right(3, "x").is_ok());
+    assert_eq!(&s.to_string(), "héxllo");
+}
@@ -0,0 +1,36 @@
+#![cfg(test)]
+use crate::error::Error;
+use crate::RefactoryString;
+use alloc::string::ToString;
+
+#[test]
+fn insert_line_before() -> Result<(), Error> {
+    let content = "fn main() {\n    let a = 1;\n    call(a);\n}\n";
+    let mut s = RefactoryString::new(content);
+
+    let index = content.find("call").unwrap();
+    s.insert_line_before(index, "let b = 2;")?;
+    s.insert_line_before(index + 2, "// Multiple\n\nlines.\n")?;
+
+    assert_eq!(
+        &s.to_string(),
+        "fn main() {\n    let a = 1;\n    let b = 2;\n    // Multiple\n\n    lines.\n    call(a);\n}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn insert_line_after() -> Result<(), Error> {
+    let content = "if x {\r\n\tfoo();\r\n}";
+    let mut s = RefactoryString::new(content);
+
+    let index = content.find(';').unwrap() + 1;
+    s.insert_line_after(index, "bar();\nbaz();")?;
+    s.insert_line_after(content.len(), "// end")?;
+
+    assert_eq!(
+        &s.to_string(),
+        "if x {\r\n\tfoo();\r\n\tbar();\r\n\tbaz();\r\n}\r\n// end"
+    );
+    Ok(())
+}
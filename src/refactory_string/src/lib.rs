@@ -1,13 +1,59 @@
+#![no_std]
+
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 mod chunk;
 mod chunk_list;
+mod edit;
+#[cfg(feature = "std")]
+mod edit_set;
 mod error;
+mod line_ending;
+mod line_index;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+mod merge;
+mod node;
 mod refactory_string;
+mod source_map;
+mod vlq;
 
+pub use crate::chunk_list::ChunkStats;
+pub use crate::edit::Edit;
+#[cfg(feature = "std")]
+pub use crate::edit_set::{EditScope, EditSet};
+pub use crate::error::Error;
+pub use crate::line_ending::LineEnding;
+pub use crate::line_index::LineIndex;
+pub use crate::merge::{merge, Conflict, Merge};
+pub use crate::node::SourceNode;
+#[cfg(feature = "syn")]
+pub use crate::node::Syn;
+#[cfg(feature = "tree-sitter")]
+pub use crate::node::TreeSitter;
 pub use crate::refactory_string::*;
+pub use crate::source_map::{
+    compose, decode_mappings, encode_mappings, OriginalLocation, OriginalPosition, Segment,
+    SourceMap, SourceMapOptions,
+};
 
 // Tests
 mod chunk_test;
+mod compact_test;
+mod edit_set_test;
+mod error_test;
+mod insert_line_test;
+mod line_ending_test;
+mod lsp_test;
+mod merge_test;
+mod node_test;
+mod overwrite_test;
+mod query_test;
 mod refactory_string_own_test;
 mod refactory_string_test;
+mod reset_test;
+mod source_map_test;
+mod std_test;
+mod tree_sitter_test;
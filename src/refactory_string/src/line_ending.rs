@@ -0,0 +1,67 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+
+/// The style of line endings used in a content.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum LineEnding {
+    /// `\n`, used on Unix-like systems.
+    #[default]
+    Lf,
+    /// `\r\n`, used on Windows.
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    /// Detect the dominant line ending style of a content. Contents without any line
+    /// ending, or with as many of each style, are considered to use `Lf`.
+    pub fn detect(content: &str) -> Self {
+        let bytes = content.as_bytes();
+        let (lf, crlf) =
+            content
+                .match_indices('\n')
+                .fold((0usize, 0usize), |(lf, crlf), (i, _)| {
+                    if i > 0 && bytes[i - 1] == b'\r' {
+                        (lf, crlf + 1)
+                    } else {
+                        (lf + 1, crlf)
+                    }
+                });
+
+        if crlf > lf {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// Convert every line ending of a content to this style.
+    pub fn normalize(self, content: &str) -> Cow<'_, str> {
+        let bytes = content.as_bytes();
+        let needs_change = content.match_indices('\n').any(|(i, _)| {
+            let is_crlf = i > 0 && bytes[i - 1] == b'\r';
+            is_crlf != (self == LineEnding::CrLf)
+        });
+        if !needs_change {
+            return Cow::Borrowed(content);
+        }
+
+        let mut result = String::with_capacity(content.len());
+        for line in content.split_inclusive('\n') {
+            match line.strip_suffix('\n') {
+                Some(line) => {
+                    result.push_str(line.strip_suffix('\r').unwrap_or(line));
+                    result.push_str(self.as_str());
+                }
+                None => result.push_str(line),
+            }
+        }
+        Cow::Owned(result)
+    }
+}
@@ -0,0 +1,84 @@
+#![cfg(test)]
+use crate::error::Error;
+use crate::source_map::SourceMapOptions;
+use crate::{LineEnding, RefactoryString};
+use alloc::string::ToString;
+
+#[test]
+fn detect() {
+    assert_eq!(LineEnding::detect(""), LineEnding::Lf);
+    assert_eq!(LineEnding::detect("a\nb\r\nc\n"), LineEnding::Lf);
+    assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), LineEnding::CrLf);
+    assert_eq!(LineEnding::detect("a\r\nb\n"), LineEnding::Lf);
+
+    assert_eq!(
+        RefactoryString::new("a\r\nb").line_ending(),
+        LineEnding::CrLf
+    );
+}
+
+#[test]
+fn normalize() {
+    assert_eq!(LineEnding::Lf.normalize("a\r\nb\nc"), "a\nb\nc");
+    assert_eq!(LineEnding::CrLf.normalize("a\r\nb\nc\n"), "a\r\nb\r\nc\r\n");
+    assert_eq!(LineEnding::CrLf.normalize("a\r\nb"), "a\r\nb");
+}
+
+#[test]
+fn normalize_inserted_content() -> Result<(), Error> {
+    let mut s = RefactoryString::new("fn a() {}\r\nfn b() {}\r\n");
+
+    s.append_left(11, "// b\n")?;
+    assert_eq!(&s.to_string(), "fn a() {}\r\n// b\nfn b() {}\r\n");
+
+    s.set_normalize_line_endings(true);
+    s.append_left(11, "// more\n")?;
+    assert_eq!(
+        &s.to_string(),
+        "fn a() {}\r\n// b\n// more\r\nfn b() {}\r\n"
+    );
+
+    s.set_normalize_line_endings(false);
+    s.append("x\n")?;
+    assert_eq!(
+        &s.to_string(),
+        "fn a() {}\r\n// b\n// more\r\nfn b() {}\r\nx\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn convert_line_endings() -> Result<(), Error> {
+    let mut s = RefactoryString::new("a\r\nb\nc\r\nd");
+    s.append_right(3, "<")?;
+    s.append_left(3, ">\r\n")?;
+    s.remove(5, 6)?;
+
+    s.convert_line_endings(LineEnding::Lf)?;
+    assert_eq!(&s.to_string(), "a\n>\n<b\n\nd");
+
+    s.convert_line_endings(LineEnding::CrLf)?;
+    assert_eq!(&s.to_string(), "a\r\n>\r\n<b\r\n\r\nd");
+
+    // Every original line still maps to itself.
+    let map = s.generate_map(&SourceMapOptions::default())?;
+    let d = map.original_position_for(4, 0).unwrap();
+    assert_eq!((d.line, d.column), (3, 0));
+    let b = map.original_position_for(2, 1).unwrap();
+    assert_eq!((b.line, b.column), (1, 0));
+
+    Ok(())
+}
+
+#[test]
+fn convert_line_endings_keeps_inserted() -> Result<(), Error> {
+    let mut s = RefactoryString::new("a\r\nb\r\n");
+    s.append_left(2, "X")?;
+    s.convert_line_endings(LineEnding::Lf)?;
+    assert_eq!(&s.to_string(), "aX\nb\n");
+
+    s.convert_line_endings(LineEnding::CrLf)?;
+    assert_eq!(&s.to_string(), "aX\r\nb\r\n");
+    Ok(())
+}
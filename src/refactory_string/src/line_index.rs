@@ -0,0 +1,72 @@
+use alloc::vec::Vec;
+
+/// An index of the line starts of a string, used to convert byte offsets into
+/// line/column locations. A RefactoryString builds one for its original content when it
+/// is created, and uses it for source maps, LSP positions and `SourceNode::source_range`.
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(content: &str) -> Self {
+        let mut line_starts = Vec::new();
+        line_starts.push(0);
+        line_starts.extend(content.match_indices('\n').map(|(i, _)| i + 1));
+        LineIndex { line_starts }
+    }
+
+    /// The line (0-based) containing the byte at `offset`.
+    pub fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|start| *start <= offset) - 1
+    }
+
+    /// The line (0-based) and column (0-based, in bytes) of the byte at `offset`.
+    #[cfg(feature = "tree-sitter")]
+    pub fn locate(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_of(offset);
+        (line, offset - self.line_starts[line])
+    }
+
+    /// The line (0-based) and column (0-based, in UTF-16 code units) of the byte at `offset`.
+    pub fn locate_utf16(&self, content: &str, offset: usize) -> (usize, usize) {
+        let line = self.line_of(offset);
+        let column = content[self.line_starts[line]..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum();
+        (line, column)
+    }
+
+    /// The byte offset of a line (0-based) and column (0-based). Columns are counted in
+    /// units given by `char_len`, for example UTF-16 code units or chars. Lines past the
+    /// end are clamped to the end of the content, and columns past the end of their line
+    /// are clamped to the end of the line (before its line ending).
+    #[cfg(any(feature = "lsp", feature = "syn"))]
+    pub fn offset_of<F: Fn(char) -> usize>(
+        &self,
+        content: &str,
+        line: usize,
+        column: usize,
+        char_len: F,
+    ) -> usize {
+        let start = match self.line_starts.get(line) {
+            Some(start) => *start,
+            None => return content.len(),
+        };
+        let line_content = &content[start..];
+        let line_content = match line_content.find('\n') {
+            Some(end) => &line_content[..end],
+            None => line_content,
+        };
+        let line_content = line_content.strip_suffix('\r').unwrap_or(line_content);
+
+        let mut remaining = column;
+        for (i, c) in line_content.char_indices() {
+            if remaining < char_len(c) {
+                return start + i;
+            }
+            remaining -= char_len(c);
+        }
+        start + line_content.len()
+    }
+}
@@ -0,0 +1,188 @@
+//! Support for the text edits of the Language Server Protocol. Positions in LSP payloads
+//! use 0-based lines, and 0-based characters counted in UTF-16 code units.
+use crate::error::Error;
+use crate::{OverwriteOptions, RefactoryString};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// A textual edit of a document. Annotated edits are accepted, but their annotation is
+/// ignored.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct VersionedTextDocumentIdentifier {
+    pub uri: String,
+    #[serde(default)]
+    pub version: Option<i32>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentEdit {
+    pub text_document: VersionedTextDocumentIdentifier,
+    pub edits: Vec<TextEdit>,
+}
+
+/// An entry of `WorkspaceEdit::document_changes`. Resource operations (creating, renaming
+/// or deleting files) are kept as raw JSON values, as they cannot be applied to a string.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum DocumentChange {
+    Edit(TextDocumentEdit),
+    ResourceOperation(serde_json::Value),
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceEdit {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<BTreeMap<String, Vec<TextEdit>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_changes: Option<Vec<DocumentChange>>,
+}
+
+impl TextEdit {
+    /// Parse a JSON array of text edits.
+    pub fn from_json(json: &str) -> serde_json::Result<Vec<TextEdit>> {
+        serde_json::from_str(json)
+    }
+
+    /// Serialize a list of text edits to a JSON array.
+    pub fn to_json(edits: &[TextEdit]) -> serde_json::Result<String> {
+        serde_json::to_string(edits)
+    }
+}
+
+impl WorkspaceEdit {
+    pub fn from_json(json: &str) -> serde_json::Result<WorkspaceEdit> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// All the text edits of a document, from both `changes` and `document_changes`.
+    pub fn text_edits_for(&self, uri: &str) -> Vec<&TextEdit> {
+        let changes = self
+            .changes
+            .iter()
+            .flat_map(|changes| changes.get(uri))
+            .flatten();
+        let document_changes = self
+            .document_changes
+            .iter()
+            .flatten()
+            .filter_map(|change| match change {
+                DocumentChange::Edit(edit) if edit.text_document.uri == uri => Some(&edit.edits),
+                _ => None,
+            })
+            .flatten();
+
+        changes.chain(document_changes).collect()
+    }
+}
+
+impl<'a> RefactoryString<'a> {
+    /// Apply LSP text edits. As required by the protocol, every range refers to the
+    /// original content, and edits inserting at the same position are applied in order.
+    /// Ranges cannot overlap, but an insertion can be at the boundary of a replacement.
+    pub fn apply_text_edits<'e, I: IntoIterator<Item = &'e TextEdit>>(
+        &mut self,
+        edits: I,
+    ) -> Result<(), Error> {
+        let original = self.original();
+        let index = self.line_index();
+        let offset = |p: &Position| {
+            index.offset_of(
+                original,
+                p.line as usize,
+                p.character as usize,
+                char::len_utf16,
+            )
+        };
+
+        let edits: Vec<(usize, usize, &str)> = edits
+            .into_iter()
+            .map(|edit| {
+                let start = offset(&edit.range.start);
+                let end = offset(&edit.range.end);
+                (start, end, edit.new_text.as_str())
+            })
+            .collect();
+
+        let mut ranges: Vec<(usize, usize)> = edits.iter().map(|&(s, e, _)| (s, e)).collect();
+        ranges.sort_unstable();
+        for pair in ranges.windows(2) {
+            let ((_, previous_end), (start, end)) = (pair[0], pair[1]);
+            if start < previous_end {
+                return Err(Error::OverlappingEdits { start, end });
+            }
+        }
+
+        // Replacements edit the original content in place, so the insertions at their
+        // boundaries are kept whatever the order of the edits.
+        let options = OverwriteOptions {
+            content_only: true,
+            ..Default::default()
+        };
+        for (start, end, new_text) in edits {
+            if start == end {
+                self.append_left(start, new_text)?;
+            } else {
+                self.overwrite_with(start, end, new_text, &options)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the text edits of a workspace edit that target the document `uri`.
+    pub fn apply_workspace_edit(&mut self, uri: &str, edit: &WorkspaceEdit) -> Result<(), Error> {
+        self.apply_text_edits(edit.text_edits_for(uri))
+    }
+
+    /// Convert the changes made to the original content into LSP text edits.
+    pub fn to_text_edits(&self) -> Vec<TextEdit> {
+        let original = self.original();
+        let index = self.line_index();
+        let position = |offset: usize| {
+            let (line, character) = index.locate_utf16(original, offset);
+            Position {
+                line: line as u32,
+                character: character as u32,
+            }
+        };
+
+        self.edits()
+            .into_iter()
+            .map(|edit| TextEdit {
+                range: Range {
+                    start: position(edit.start),
+                    end: position(edit.end),
+                },
+                new_text: edit.content,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,155 @@
+#![cfg(all(test, feature = "lsp"))]
+use crate::error::Error;
+use crate::lsp::{Position, Range, TextEdit, WorkspaceEdit};
+use crate::RefactoryString;
+use alloc::string::ToString;
+use alloc::vec;
+
+#[test]
+fn apply_text_edits() -> Result<(), Error> {
+    let content = "let 😀 = \"é\";\nprint(😀);\n";
+    let edits = TextEdit::from_json(
+        r#"[
+            { "range": { "start": { "line": 0, "character": 4 }, "end": { "line": 0, "character": 6 } }, "newText": "smile" },
+            { "range": { "start": { "line": 1, "character": 6 }, "end": { "line": 1, "character": 8 } }, "newText": "smile" },
+            { "range": { "start": { "line": 0, "character": 10 }, "end": { "line": 0, "character": 11 } }, "newText": "e" },
+            { "range": { "start": { "line": 1, "character": 99 }, "end": { "line": 1, "character": 99 } }, "newText": " // end" }
+        ]"#,
+    )
+    .unwrap();
+
+    let mut s = RefactoryString::new(content);
+    s.apply_text_edits(&edits)?;
+    assert_eq!(&s.to_string(), "let smile = \"e\";\nprint(smile); // end\n");
+    Ok(())
+}
+
+#[test]
+fn apply_text_edits_at_boundaries() -> Result<(), Error> {
+    let edits = TextEdit::from_json(
+        r#"[
+            { "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 3 } }, "newText": "foo" },
+            { "range": { "start": { "line": 0, "character": 3 }, "end": { "line": 0, "character": 3 } }, "newText": "bar" },
+            { "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } }, "newText": "<" }
+        ]"#,
+    )
+    .unwrap();
+
+    let mut s = RefactoryString::new("abcdef");
+    s.apply_text_edits(&edits)?;
+    assert_eq!(&s.to_string(), "<foobardef");
+
+    let mut s = RefactoryString::new("abcdef");
+    s.apply_text_edits(edits.iter().rev())?;
+    assert_eq!(&s.to_string(), "<foobardef");
+    Ok(())
+}
+
+#[test]
+fn apply_overlapping_text_edits() -> Result<(), Error> {
+    let edits = TextEdit::from_json(
+        r#"[
+            { "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 3 } }, "newText": "foo" },
+            { "range": { "start": { "line": 0, "character": 2 }, "end": { "line": 0, "character": 4 } }, "newText": "bar" }
+        ]"#,
+    )
+    .unwrap();
+
+    let mut s = RefactoryString::new("abcdef");
+    assert_eq!(
+        s.apply_text_edits(&edits),
+        Err(Error::OverlappingEdits { start: 2, end: 4 })
+    );
+    assert_eq!(&s.to_string(), "abcdef");
+
+    let insert = TextEdit::from_json(
+        r#"[{ "range": { "start": { "line": 0, "character": 1 }, "end": { "line": 0, "character": 1 } }, "newText": "x" }]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        s.apply_text_edits(edits.iter().take(1).chain(&insert)),
+        Err(Error::OverlappingEdits { start: 1, end: 1 })
+    );
+    Ok(())
+}
+
+#[test]
+fn apply_workspace_edit() -> Result<(), Error> {
+    let edit = WorkspaceEdit::from_json(
+        r#"{
+            "changes": {
+                "file:///a.rs": [
+                    { "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } }, "newText": "// a\n" }
+                ]
+            },
+            "documentChanges": [
+                { "kind": "create", "uri": "file:///c.rs" },
+                {
+                    "textDocument": { "uri": "file:///a.rs", "version": 3 },
+                    "edits": [
+                        { "range": { "start": { "line": 0, "character": 3 }, "end": { "line": 0, "character": 6 } }, "newText": "bar", "annotationId": "x" }
+                    ]
+                },
+                {
+                    "textDocument": { "uri": "file:///b.rs", "version": null },
+                    "edits": [
+                        { "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 2 } }, "newText": "" }
+                    ]
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let mut s = RefactoryString::new("fn foo() {}");
+    s.apply_workspace_edit("file:///a.rs", &edit)?;
+    assert_eq!(&s.to_string(), "// a\nfn bar() {}");
+    Ok(())
+}
+
+#[test]
+fn to_text_edits() -> Result<(), Error> {
+    let content = "a\r\nbé😀c";
+    let mut s = RefactoryString::new(content);
+    s.overwrite(10, 11, "d")?;
+    s.append_right(3, "x")?;
+
+    let edits = s.to_text_edits();
+    assert_eq!(
+        edits,
+        vec![
+            TextEdit {
+                range: Range {
+                    start: Position {
+                        line: 1,
+                        character: 0
+                    },
+                    end: Position {
+                        line: 1,
+                        character: 0
+                    },
+                },
+                new_text: "x".to_string(),
+            },
+            TextEdit {
+                range: Range {
+                    start: Position {
+                        line: 1,
+                        character: 4
+                    },
+                    end: Position {
+                        line: 1,
+                        character: 5
+                    },
+                },
+                new_text: "d".to_string(),
+            },
+        ]
+    );
+
+    // Applying the edits back gives the same output.
+    let mut other = RefactoryString::new(content);
+    other.apply_text_edits(&TextEdit::from_json(&TextEdit::to_json(&edits).unwrap()).unwrap())?;
+    assert_eq!(other.to_string(), s.to_string());
+    Ok(())
+}
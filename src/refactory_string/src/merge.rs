@@ -0,0 +1,129 @@
+use crate::edit::Edit;
+use crate::error::Error;
+use crate::{OverwriteOptions, RefactoryString};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A region of the original content that both sides of a merge changed differently.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conflict {
+    /// Start of the conflicting region in the original content.
+    pub start: usize,
+    /// End of the conflicting region in the original content.
+    pub end: usize,
+    /// What the first RefactoryString replaces the region with.
+    pub a: String,
+    /// What the second RefactoryString replaces the region with.
+    pub b: String,
+}
+
+/// The result of merging two RefactoryStrings. Conflicting regions are left untouched in
+/// `result`, and can be resolved by overwriting them.
+pub struct Merge<'a> {
+    pub result: RefactoryString<'a>,
+    pub conflicts: Vec<Conflict>,
+}
+
+enum Side {
+    A,
+    B,
+}
+
+/// Whether an edit overlaps a region in a way that makes the order of changes ambiguous.
+/// Insertions at the boundaries of a region are not considered overlapping, except if the
+/// region itself is an insertion at the same index.
+fn overlaps(edit: &Edit, start: usize, end: usize) -> bool {
+    if start == end && edit.is_insertion() {
+        edit.start == start
+    } else {
+        edit.start < end && start < edit.end
+    }
+}
+
+/// The content that replaces the original region `start..end` once edits are applied.
+fn replacement(original: &str, start: usize, end: usize, edits: &[&Edit]) -> String {
+    let mut result = String::new();
+    let mut cursor = start;
+    for edit in edits {
+        result.push_str(&original[cursor..edit.start]);
+        result.push_str(&edit.content);
+        cursor = edit.end;
+    }
+    result.push_str(&original[cursor..end]);
+    result
+}
+
+/// Merge the changes of two RefactoryStrings made on the same original content (a
+/// three-way merge). Changes to disjoint regions are all kept, and identical changes are
+/// only applied once. Regions that both sides changed differently are reported as
+/// conflicts instead.
+pub fn merge<'a>(a: &RefactoryString<'a>, b: &RefactoryString<'a>) -> Result<Merge<'a>, Error> {
+    let original = a.original();
+    if original != b.original() {
+        return Err(Error::MismatchedOriginals);
+    }
+
+    // Edits touching each other are kept apart, so an insertion next to a replacement is
+    // merged on its own.
+    let a_edits = a.chunk_edits();
+    let b_edits = b.chunk_edits();
+    let mut all: Vec<(&Edit, Side)> = a_edits
+        .iter()
+        .map(|e| (e, Side::A))
+        .chain(b_edits.iter().map(|e| (e, Side::B)))
+        .collect();
+    all.sort_by_key(|(e, _)| (e.start, e.end));
+
+    let options = OverwriteOptions {
+        content_only: true,
+        ..Default::default()
+    };
+    let mut result = RefactoryString::new(original);
+    let mut conflicts = Vec::new();
+    let mut it = all.into_iter().peekable();
+
+    while let Some((first, side)) = it.next() {
+        let (mut start, mut end) = (first.start, first.end);
+        let (mut group_a, mut group_b) = (Vec::new(), Vec::new());
+        match side {
+            Side::A => group_a.push(first),
+            Side::B => group_b.push(first),
+        }
+
+        while let Some((edit, side)) = it.next_if(|(e, _)| overlaps(e, start, end)) {
+            start = start.min(edit.start);
+            end = end.max(edit.end);
+            match side {
+                Side::A => group_a.push(edit),
+                Side::B => group_b.push(edit),
+            }
+        }
+
+        let edits = match (group_a.is_empty(), group_b.is_empty()) {
+            (_, true) => group_a,
+            (true, false) => group_b,
+            (false, false) => {
+                let a = replacement(original, start, end, &group_a);
+                let b = replacement(original, start, end, &group_b);
+                if a == b {
+                    group_a
+                } else {
+                    conflicts.push(Conflict { start, end, a, b });
+                    continue;
+                }
+            }
+        };
+
+        // Replacements edit the original content in place, so the edits touching their
+        // boundaries are kept.
+        for edit in edits {
+            if edit.is_insertion() {
+                result.append_left(edit.start, &edit.content)?;
+            } else {
+                result.overwrite_with(edit.start, edit.end, &edit.content, &options)?;
+            }
+        }
+    }
+
+    Ok(Merge { result, conflicts })
+}
@@ -0,0 +1,189 @@
+#![cfg(test)]
+use crate::error::Error;
+use crate::{merge, Conflict, Edit, RefactoryString};
+use alloc::string::ToString;
+use alloc::vec;
+
+#[test]
+fn edits() -> Result<(), Error> {
+    let mut s = RefactoryString::new("let a = 1;");
+    s.overwrite(4, 5, "b")?;
+    s.append_left(10, " // b")?;
+    s.remove(8, 9)?;
+
+    assert_eq!(
+        s.edits(),
+        vec![
+            Edit {
+                start: 4,
+                end: 5,
+                content: "b".to_string()
+            },
+            Edit {
+                start: 8,
+                end: 9,
+                content: "".to_string()
+            },
+            Edit {
+                start: 10,
+                end: 10,
+                content: " // b".to_string()
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn disjoint() -> Result<(), Error> {
+    let original = "let a = 1;\nlet b = 2;\n";
+    let mut a = RefactoryString::new(original);
+    let mut b = RefactoryString::new(original);
+
+    a.overwrite(4, 5, "x")?;
+    a.prepend("// a\n")?;
+    b.overwrite(15, 16, "y")?;
+    b.append("// b\n")?;
+
+    let merged = merge(&a, &b)?;
+    assert!(merged.conflicts.is_empty());
+    assert_eq!(
+        &merged.result.to_string(),
+        "// a\nlet x = 1;\nlet y = 2;\n// b\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn identical_edits() -> Result<(), Error> {
+    let original = "foo(bar)";
+    let mut a = RefactoryString::new(original);
+    let mut b = RefactoryString::new(original);
+
+    a.overwrite(0, 3, "baz")?;
+    b.overwrite(0, 3, "baz")?;
+    b.append(";")?;
+
+    let merged = merge(&a, &b)?;
+    assert!(merged.conflicts.is_empty());
+    assert_eq!(&merged.result.to_string(), "baz(bar);");
+    Ok(())
+}
+
+#[test]
+fn edits_at_boundaries() -> Result<(), Error> {
+    let original = "0123456789";
+    let mut a = RefactoryString::new(original);
+    let mut b = RefactoryString::new(original);
+
+    a.append_left(5, "X")?;
+    a.append_left(3, "Z")?;
+    b.overwrite(3, 5, "y")?;
+
+    let merged = merge(&a, &b)?;
+    assert!(merged.conflicts.is_empty());
+    assert_eq!(&merged.result.to_string(), "012ZyX56789");
+    Ok(())
+}
+
+#[test]
+fn adjacent_overwrites() -> Result<(), Error> {
+    let original = "0123456789";
+    let mut a = RefactoryString::new(original);
+    let mut b = RefactoryString::new(original);
+
+    a.overwrite(2, 4, "A")?;
+    b.overwrite(4, 6, "B")?;
+
+    let merged = merge(&a, &b)?;
+    assert!(merged.conflicts.is_empty());
+    assert_eq!(&merged.result.to_string(), "01AB6789");
+    assert_eq!(&merge(&b, &a)?.result.to_string(), "01AB6789");
+    Ok(())
+}
+
+#[test]
+fn identical_insertions_next_to_overwrite() -> Result<(), Error> {
+    let original = "0123456789";
+    let mut a = RefactoryString::new(original);
+    let mut b = RefactoryString::new(original);
+
+    a.append_left(4, "X")?;
+    b.append_left(4, "X")?;
+    b.overwrite_with(4, 6, "B", &Default::default())?;
+
+    let merged = merge(&a, &b)?;
+    assert!(merged.conflicts.is_empty());
+    assert_eq!(&merged.result.to_string(), "0123XB6789");
+    Ok(())
+}
+
+#[test]
+fn conflicting_insertions_next_to_overwrite() -> Result<(), Error> {
+    let original = "0123456789";
+    let mut a = RefactoryString::new(original);
+    let mut b = RefactoryString::new(original);
+
+    a.append_left(4, "X")?;
+    a.overwrite_with(4, 6, "B", &Default::default())?;
+    b.append_left(4, "Y")?;
+
+    let merged = merge(&a, &b)?;
+    assert_eq!(
+        merged.conflicts,
+        vec![Conflict {
+            start: 4,
+            end: 4,
+            a: "X".to_string(),
+            b: "Y".to_string(),
+        }]
+    );
+    assert_eq!(&merged.result.to_string(), "0123B6789");
+    Ok(())
+}
+
+#[test]
+fn conflicts() -> Result<(), Error> {
+    let original = "foo(bar, baz)";
+    let mut a = RefactoryString::new(original);
+    let mut b = RefactoryString::new(original);
+
+    a.overwrite(4, 7, "one")?;
+    a.overwrite(9, 12, "two")?;
+    b.overwrite(4, 12, "both")?;
+    a.append_left(0, "a")?;
+    b.append_left(0, "b")?;
+    a.append(";")?;
+
+    let merged = merge(&a, &b)?;
+    assert_eq!(
+        merged.conflicts,
+        vec![
+            Conflict {
+                start: 0,
+                end: 0,
+                a: "a".to_string(),
+                b: "b".to_string(),
+            },
+            Conflict {
+                start: 4,
+                end: 12,
+                a: "one, two".to_string(),
+                b: "both".to_string(),
+            },
+        ]
+    );
+    assert_eq!(&merged.result.to_string(), "foo(bar, baz);");
+
+    let mut result = merged.result;
+    result.overwrite(4, 12, &merged.conflicts[1].b)?;
+    assert_eq!(&result.to_string(), "foo(both);");
+    Ok(())
+}
+
+#[test]
+fn mismatched_originals() {
+    let a = RefactoryString::new("a");
+    let b = RefactoryString::new("b");
+    assert!(matches!(merge(&a, &b), Err(Error::MismatchedOriginals)));
+}
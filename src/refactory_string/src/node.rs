@@ -0,0 +1,120 @@
+use crate::error::Error;
+use crate::line_index::LineIndex;
+use crate::RefactoryString;
+#[cfg(feature = "tree-sitter")]
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// A node of a syntax tree that knows its location in the original content. `P` is a
+/// marker type for the parser that produced the node, which allows implementing this
+/// trait for the nodes of multiple parsers.
+pub trait SourceNode<P> {
+    /// The byte range of the node in the original content. `index` is the line index of
+    /// the original content, built once per RefactoryString.
+    fn source_range(&self, original: &str, index: &LineIndex) -> Range<usize>;
+}
+
+/// Marker type for the nodes of the `syn` crate (anything that implements
+/// `syn::spanned::Spanned`). Spans must come from parsing the original content.
+#[cfg(feature = "syn")]
+pub enum Syn {}
+
+#[cfg(feature = "syn")]
+impl<T: syn::spanned::Spanned + ?Sized> SourceNode<Syn> for T {
+    fn source_range(&self, original: &str, index: &LineIndex) -> Range<usize> {
+        let span = self.span();
+        // Lines of spans are 1-based, and columns are counted in chars.
+        let offset = |location: proc_macro2::LineColumn| {
+            index.offset_of(
+                original,
+                location.line.saturating_sub(1),
+                location.column,
+                |_| 1,
+            )
+        };
+
+        offset(span.start())..offset(span.end())
+    }
+}
+
+/// Marker type for the nodes of the `tree-sitter` crate. Nodes must come from parsing
+/// the original content.
+#[cfg(feature = "tree-sitter")]
+pub enum TreeSitter {}
+
+#[cfg(feature = "tree-sitter")]
+impl SourceNode<TreeSitter> for tree_sitter::Node<'_> {
+    fn source_range(&self, _original: &str, _index: &LineIndex) -> Range<usize> {
+        self.byte_range()
+    }
+}
+
+impl<'a> RefactoryString<'a> {
+    /// Overwrite the content of a node.
+    pub fn overwrite_node<P, N: SourceNode<P> + ?Sized>(
+        &mut self,
+        node: &N,
+        content: &str,
+    ) -> Result<(), Error> {
+        let range = node.source_range(self.original(), self.line_index());
+        self.overwrite(range.start, range.end, content)
+    }
+
+    /// Remove the content of a node.
+    pub fn remove_node<P, N: SourceNode<P> + ?Sized>(&mut self, node: &N) -> Result<(), Error> {
+        let range = node.source_range(self.original(), self.line_index());
+        self.remove(range.start, range.end)
+    }
+
+    /// Insert content before and after a node. Wrapping the same node multiple times
+    /// nests the last wrap outside of the previous ones.
+    pub fn wrap_node<P, N: SourceNode<P> + ?Sized>(
+        &mut self,
+        node: &N,
+        before: &str,
+        after: &str,
+    ) -> Result<(), Error> {
+        let range = node.source_range(self.original(), self.line_index());
+        self.prepend_right(range.start, before)?;
+        self.append_left(range.end, after)
+    }
+
+    /// The changes made to the original content, as a list of `tree_sitter::InputEdit`.
+    /// The edits are sorted from the end of the content to its start, which is the order
+    /// they must be passed to `Tree::edit` for an incremental parse of the output.
+    #[cfg(feature = "tree-sitter")]
+    pub fn input_edits(&self) -> Vec<tree_sitter::InputEdit> {
+        let index = self.line_index();
+        let point = |offset: usize| {
+            let (row, column) = index.locate(offset);
+            tree_sitter::Point { row, column }
+        };
+
+        self.edits()
+            .iter()
+            .rev()
+            .map(|edit| {
+                let start_position = point(edit.start);
+                let new_end_position = match edit.content.rfind('\n') {
+                    Some(i) => tree_sitter::Point {
+                        row: start_position.row + edit.content.matches('\n').count(),
+                        column: edit.content.len() - i - 1,
+                    },
+                    None => tree_sitter::Point {
+                        row: start_position.row,
+                        column: start_position.column + edit.content.len(),
+                    },
+                };
+
+                tree_sitter::InputEdit {
+                    start_byte: edit.start,
+                    old_end_byte: edit.end,
+                    new_end_byte: edit.start + edit.content.len(),
+                    start_position,
+                    old_end_position: point(edit.end),
+                    new_end_position,
+                }
+            })
+            .collect()
+    }
+}
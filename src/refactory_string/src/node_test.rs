@@ -0,0 +1,53 @@
+#![cfg(all(test, feature = "syn"))]
+use crate::error::Error;
+use crate::RefactoryString;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use syn::visit::Visit;
+
+struct IdentCollector<'ast> {
+    name: &'static str,
+    found: Vec<&'ast syn::Ident>,
+}
+
+impl<'ast> Visit<'ast> for IdentCollector<'ast> {
+    fn visit_ident(&mut self, ident: &'ast syn::Ident) {
+        if ident == self.name {
+            self.found.push(ident);
+        }
+    }
+}
+
+#[test]
+fn rename_variable() -> Result<(), Error> {
+    let content = "fn compute() -> i32 {\n    let x = 1;\n    let y = x + 2;\n    x * y // x\n}\n";
+    let file = syn::parse_file(content).unwrap();
+
+    let mut collector = IdentCollector {
+        name: "x",
+        found: Vec::new(),
+    };
+    collector.visit_file(&file);
+    assert_eq!(collector.found.len(), 3);
+
+    let mut s = RefactoryString::new(content);
+    for ident in collector.found {
+        s.overwrite_node(ident, "value")?;
+    }
+    assert_eq!(
+        &s.to_string(),
+        "fn compute() -> i32 {\n    let value = 1;\n    let y = value + 2;\n    value * y // x\n}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn remove_node() -> Result<(), Error> {
+    let content = "use std::fmt;\n\nfn λ() {}\nfn keep() {}\n";
+    let file = syn::parse_file(content).unwrap();
+
+    let mut s = RefactoryString::new(content);
+    s.remove_node(&file.items[1])?;
+    assert_eq!(&s.to_string(), "use std::fmt;\n\n\nfn keep() {}\n");
+    Ok(())
+}
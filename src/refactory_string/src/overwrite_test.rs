@@ -0,0 +1,71 @@
+#![cfg(test)]
+use crate::error::Error;
+use crate::{OverwriteOptions, RefactoryString, SourceMapOptions};
+use alloc::string::ToString;
+
+fn with_insertions(content: &str) -> Result<RefactoryString<'_>, Error> {
+    let mut s = RefactoryString::new(content);
+    s.append_left(4, "<")?;
+    s.append_right(4, "(")?;
+    s.append_left(6, "|")?;
+    s.append_left(9, ")")?;
+    s.append_right(9, ">")?;
+    Ok(s)
+}
+
+#[test]
+fn overwrite_content_only() -> Result<(), Error> {
+    let mut s = with_insertions("let value = 1;")?;
+    assert_eq!(&s.to_string(), "let <(va|lue)> = 1;");
+
+    let options = OverwriteOptions {
+        content_only: true,
+        ..OverwriteOptions::default()
+    };
+    s.overwrite_with(4, 9, "x", &options)?;
+    assert_eq!(&s.to_string(), "let <(x)> = 1;");
+    Ok(())
+}
+
+#[test]
+fn overwrite_with_default() -> Result<(), Error> {
+    let mut s = with_insertions("let value = 1;")?;
+    s.overwrite_with(4, 9, "x", &OverwriteOptions::default())?;
+    assert_eq!(&s.to_string(), "let <x> = 1;");
+
+    // Overwriting the same range again replaces the previous content.
+    s.overwrite_with(4, 9, "y", &OverwriteOptions::default())?;
+    assert_eq!(&s.to_string(), "let <y> = 1;");
+
+    assert_eq!(
+        s.overwrite_with(4, 4, "x", &OverwriteOptions::default()),
+        Err(Error::InvalidRange {
+            start: 4,
+            end: 4,
+            length: 14
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn overwrite_store_name() -> Result<(), Error> {
+    let content = "let value = value + 1;";
+    let mut s = RefactoryString::new(content);
+    let options = OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+    };
+    s.overwrite_with(4, 9, "v", &options)?;
+    s.overwrite_with(12, 17, "v", &options)?;
+    s.overwrite_with(20, 21, "2", &OverwriteOptions::default())?;
+    assert_eq!(&s.to_string(), "let v = v + 2;");
+
+    let map = s.generate_map(&SourceMapOptions::default())?;
+    assert_eq!(map.names, ["value"]);
+    let position = map.original_position_for(0, 8).unwrap();
+    assert_eq!((position.column, position.name), (12, Some("value")));
+    let position = map.original_position_for(0, 12).unwrap();
+    assert_eq!((position.column, position.name), (20, None));
+    Ok(())
+}
@@ -0,0 +1,103 @@
+#![cfg(test)]
+use crate::edit::Edit;
+use crate::error::Error;
+use crate::RefactoryString;
+use alloc::string::String;
+use alloc::vec;
+
+#[test]
+fn original() -> Result<(), Error> {
+    let mut s = RefactoryString::new("let a = 1;");
+    assert!(!s.has_changed());
+
+    s.append_left(4, "")?;
+    assert!(!s.has_changed());
+
+    s.overwrite(4, 5, "b")?;
+    assert!(s.has_changed());
+    assert_eq!(s.original(), "let a = 1;");
+    Ok(())
+}
+
+#[test]
+fn is_removed() -> Result<(), Error> {
+    let mut s = RefactoryString::new("0123456789");
+    s.remove(2, 4)?;
+    s.overwrite(6, 8, "x")?;
+
+    assert!(!s.is_removed(1)?);
+    assert!(s.is_removed(2)?);
+    assert!(s.is_removed(3)?);
+    assert!(!s.is_removed(4)?);
+    assert!(s.is_removed(7)?);
+    assert!(!s.is_removed(10)?);
+    assert_eq!(
+        s.is_removed(11),
+        Err(Error::IndexOutOfBound {
+            index: 11,
+            length: 10
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn is_range_untouched() -> Result<(), Error> {
+    let mut s = RefactoryString::new("0123456789");
+    s.append_left(2, "a")?;
+    s.prepend_right(5, "b")?;
+    s.remove(8, 9)?;
+
+    assert!(s.is_range_untouched(0, 2)?);
+    assert!(s.is_range_untouched(2, 5)?);
+    assert!(!s.is_range_untouched(1, 3)?);
+    assert!(!s.is_range_untouched(4, 6)?);
+    assert!(s.is_range_untouched(5, 8)?);
+    assert!(!s.is_range_untouched(7, 9)?);
+    assert!(s.is_range_untouched(9, 10)?);
+    Ok(())
+}
+
+#[test]
+fn edits_in() -> Result<(), Error> {
+    let mut s = RefactoryString::new("0123456789");
+    s.append_left(2, "a")?;
+    s.overwrite(5, 7, "b")?;
+
+    assert_eq!(s.edits_in(3, 4)?, vec![]);
+    assert_eq!(
+        s.edits_in(0, 2)?,
+        vec![Edit {
+            start: 2,
+            end: 2,
+            content: String::from("a")
+        }]
+    );
+    assert_eq!(
+        s.edits_in(6, 10)?,
+        vec![Edit {
+            start: 5,
+            end: 7,
+            content: String::from("b")
+        }]
+    );
+    assert_eq!(s.edits_in(0, 10)?, s.edits());
+    Ok(())
+}
+
+#[test]
+fn inserted_at() -> Result<(), Error> {
+    let mut s = RefactoryString::new("0123456789");
+    s.append_left(5, "A")?;
+    s.prepend_left(5, "B")?;
+    s.append_right(5, "c")?;
+    s.append_right(0, "d")?;
+
+    let (left, right) = s.inserted_at(5)?;
+    assert_eq!((left.as_ref(), right.as_ref()), ("BA", "c"));
+    let (left, right) = s.inserted_at(0)?;
+    assert_eq!((left.as_ref(), right.as_ref()), ("", "d"));
+    let (left, right) = s.inserted_at(3)?;
+    assert_eq!((left.as_ref(), right.as_ref()), ("", ""));
+    Ok(())
+}
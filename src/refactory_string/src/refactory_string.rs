@@ -1,12 +1,43 @@
+use crate::chunk_list::{ChunkList, ChunkStats};
+use crate::edit::{push_edit, Edit};
 use crate::error::Error;
-use crate::chunk_list::ChunkList;
+use crate::line_ending::LineEnding;
+use crate::line_index::LineIndex;
+use crate::source_map::{compose, OriginalLocation, Segment, SourceMap, SourceMapOptions};
+use alloc::borrow::Cow;
 use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Options used when overwriting content with `RefactoryString::overwrite_with`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct OverwriteOptions {
+    /// Only replace the original content, keeping the content inserted at the start and
+    /// end of the range.
+    pub content_only: bool,
+
+    /// Name the new content after the original content in generated source maps, like
+    /// a renamed identifier.
+    pub store_name: bool,
+}
 
 /// A RefactoryBuffer specialization that only accepts and returns UTF-8 strings. This is
 /// what should be used when modifying a source string/file content. It uses RefactoryBuffer
 /// and converts everything conveniently.
+///
+/// A RefactoryString is `Send` and `Sync`. Modifying it requires `&mut self`; to record
+/// edits from multiple threads, use an `EditSet` (with the `std` feature).
 pub struct RefactoryString<'a> {
-    chunks: ChunkList<'a>
+    original: &'a str,
+    chunks: ChunkList<'a>,
+    input_map: Option<SourceMap>,
+    normalize_line_endings: Option<LineEnding>,
+    /// The line index of the original content, shared by every conversion between
+    /// offsets and line/column locations.
+    line_index: LineIndex,
+    /// The dominant line ending style of the original content.
+    line_ending: LineEnding,
 }
 
 impl<'a> RefactoryString<'a> {
@@ -14,22 +45,371 @@ impl<'a> RefactoryString<'a> {
     /// owns every changes made to it.
     pub fn new(content: &'a str) -> RefactoryString<'a> {
         RefactoryString {
-            chunks:ChunkList::new(content)
+            original: content,
+            chunks: ChunkList::new(content),
+            input_map: None,
+            normalize_line_endings: None,
+            line_index: LineIndex::new(content),
+            line_ending: LineEnding::detect(content),
+        }
+    }
+
+    /// The line index of the original content, which is built once as the original
+    /// content never changes.
+    pub(crate) fn line_index(&self) -> &LineIndex {
+        &self.line_index
+    }
+
+    /// Set the source map of the original content, if it is itself the output of a
+    /// transformation. Maps generated afterward will be composed with it, so they point
+    /// to the sources of the input map.
+    pub fn set_input_map(&mut self, map: SourceMap) {
+        self.input_map = Some(map);
+    }
+
+    /// The original content, without any change.
+    pub fn original(&self) -> &'a str {
+        self.original
+    }
+
+    /// Whether any change was made to the original content.
+    pub fn has_changed(&self) -> bool {
+        let arena = self.chunks.arena();
+        self.chunks.iter().any(|chunk| {
+            let mut inserted = chunk.left.iter().chain(chunk.right.iter());
+            (chunk.content.is_none() && chunk.start < chunk.end)
+                || chunk.edited(arena).is_some()
+                || inserted.any(|i| !i.is_empty())
+        })
+    }
+
+    /// Whether the original character at the index was removed.
+    pub fn is_removed(&self, index: usize) -> Result<bool, Error> {
+        self.check_index(index)?;
+        Ok(self
+            .chunks
+            .iter()
+            .find(|chunk| index >= chunk.start && index < chunk.end)
+            .is_some_and(|chunk| chunk.content.is_none()))
+    }
+
+    /// Whether the original content between two indices is neither removed nor edited,
+    /// and nothing was inserted inside of it. Content inserted exactly at `start` or `end`
+    /// does not count.
+    pub fn is_range_untouched(&self, start: usize, end: usize) -> Result<bool, Error> {
+        self.check_range(start, end)?;
+        let arena = self.chunks.arena();
+        let inside = |index: usize| index > start && index < end;
+
+        for chunk in self.chunks.iter() {
+            if chunk.start >= end {
+                break;
+            }
+            if chunk.end <= start {
+                continue;
+            }
+            if chunk.content.is_none() || chunk.edited(arena).is_some() {
+                return Ok(false);
+            }
+            let left = chunk.left.as_ref().filter(|_| inside(chunk.start));
+            let right = chunk.right.as_ref().filter(|_| inside(chunk.end));
+            if left.into_iter().chain(right).any(|i| !i.is_empty()) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// The changes (as returned by `edits`) that overlap or touch the range between two
+    /// indices.
+    pub fn edits_in(&self, start: usize, end: usize) -> Result<Vec<Edit>, Error> {
+        self.check_range(start, end)?;
+        let mut edits = self.edits();
+        edits.retain(|edit| edit.start <= end && edit.end >= start);
+        Ok(edits)
+    }
+
+    /// The content inserted to the left and to the right of the index.
+    pub fn inserted_at(&self, index: usize) -> Result<(Cow<'_, str>, Cow<'_, str>), Error> {
+        self.check_index(index)?;
+        let arena = self.chunks.arena();
+        let mut left = Cow::Borrowed("");
+        let mut right = Cow::Borrowed("");
+
+        for chunk in self.chunks.iter() {
+            if chunk.start > index {
+                break;
+            }
+            if chunk.end == index {
+                if let Some(ref inserted) = chunk.right {
+                    left = inserted.to_str(arena);
+                }
+            }
+            if chunk.start == index {
+                if let Some(ref inserted) = chunk.left {
+                    right = inserted.to_str(arena);
+                }
+            }
+        }
+        Ok((left, right))
+    }
+
+    /// The list of changes made to the original content, sorted by index. Changes that
+    /// touch each other are merged together.
+    pub fn edits(&self) -> Vec<Edit> {
+        let mut edits = Vec::new();
+        for edit in self.chunk_edits() {
+            push_edit(&mut edits, edit.start, edit.end, &edit.content);
+        }
+        edits
+    }
+
+    /// The changes made to the original content in output order. Unlike `edits`, content
+    /// inserted next to a replacement is a separate edit, unless it was inserted by
+    /// `overwrite` in place of the removed content.
+    pub(crate) fn chunk_edits(&self) -> Vec<Edit> {
+        let arena = self.chunks.arena();
+        let mut edits: Vec<Edit> = Vec::new();
+        // Whether the last edit is content inserted by `overwrite`, waiting for the
+        // removal it replaces.
+        let mut overwriting = false;
+
+        for chunk in self.chunks.iter() {
+            for (piece, overwrite) in chunk.left.iter().flat_map(|l| l.tagged_pieces(arena)) {
+                push_insertion(&mut edits, &mut overwriting, chunk.start, piece, overwrite);
+            }
+
+            let replaced = match (&chunk.content, chunk.edited(arena)) {
+                (_, Some(edited)) => Some(edited),
+                (None, None) if chunk.start < chunk.end => Some(""),
+                _ => None,
+            };
+            if let Some(content) = replaced {
+                match edits.last_mut() {
+                    Some(last)
+                        if last.end == chunk.start && (overwriting || !last.is_insertion()) =>
+                    {
+                        last.end = chunk.end;
+                        last.content.push_str(content);
+                    }
+                    _ => edits.push(Edit {
+                        start: chunk.start,
+                        end: chunk.end,
+                        content: String::from(content),
+                    }),
+                }
+                overwriting = false;
+            }
+
+            for (piece, overwrite) in chunk.right.iter().flat_map(|r| r.tagged_pieces(arena)) {
+                push_insertion(&mut edits, &mut overwriting, chunk.end, piece, overwrite);
+            }
         }
+        edits
+    }
+
+    /// The dominant line ending style of the original content.
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// When enabled, line endings of content inserted afterward are converted to the
+    /// dominant style of the original content.
+    pub fn set_normalize_line_endings(&mut self, normalize: bool) {
+        self.normalize_line_endings = if normalize {
+            Some(self.line_ending())
+        } else {
+            None
+        };
+    }
+
+    /// Convert all line endings of the output to the given style. Line endings of the
+    /// original content are edited in place, so they keep their mappings, and line endings
+    /// of the inserted content are rewritten. Removed or already edited parts of the
+    /// original content are left untouched.
+    pub fn convert_line_endings(&mut self, ending: LineEnding) -> Result<(), Error> {
+        let bytes = self.original.as_bytes();
+        let mut edits = Vec::new();
+        let mut previous_is_pristine = false;
+
+        for chunk in self.chunks.iter() {
+            let content = match (chunk.content, &chunk.edited) {
+                (Some(content), None) => Some(content),
+                _ => None,
+            };
+
+            for (i, _) in content.iter().flat_map(|c| c.match_indices('\n')) {
+                let i = chunk.start + i;
+                let has_cr =
+                    i > 0 && bytes[i - 1] == b'\r' && (i > chunk.start || previous_is_pristine);
+
+                // Only the `\r` is removed, as content can be inserted before the `\n`.
+                match ending {
+                    LineEnding::Lf if has_cr => edits.push((i - 1, i, "")),
+                    LineEnding::CrLf if !has_cr => edits.push((i, i + 1, "\r\n")),
+                    _ => {}
+                }
+            }
+
+            if chunk.start < chunk.end {
+                previous_is_pristine = content.is_some();
+            }
+        }
+
+        for (start, end, content) in edits {
+            self.chunks.edit(start, end, content, true, false)?;
+        }
+        self.chunks.normalize_inserted(ending);
+        if self.normalize_line_endings.is_some() {
+            self.normalize_line_endings = Some(ending);
+        }
+
+        Ok(())
     }
 
     /// The original length of the content it contains.
     pub fn len(&self) -> usize {
-        self.chunks.iter().fold(0, |a,x| a + x.len())
+        self.chunks.iter().fold(0, |a, x| a + x.len())
     }
 
-    /// Serialize the changes to a string.
-    pub fn to_string(&self) -> String {
-        let mut s = String::new();
-        for it in self.chunks.iter() {
-            s.push_str(&it.to_string());
+    /// Whether the original content is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write the changed content to a writer, without serializing it to a string first.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let arena = self.chunks.arena();
+        for chunk in self.chunks.iter() {
+            for piece in chunk.pieces(arena) {
+                writer.write_all(piece.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Generate a source map from the changes, mapping the output back to the original
+    /// content (or to the sources of the input map, if one was set).
+    pub fn generate_map(&self, options: &SourceMapOptions) -> Result<SourceMap, Error> {
+        let index = &self.line_index;
+        let arena = self.chunks.arena();
+        let mut mappings: Vec<Vec<Segment>> = vec![Vec::new()];
+        let mut names: Vec<String> = Vec::new();
+        let mut column = 0;
+
+        for chunk in self.chunks.iter() {
+            for left in chunk.left.iter().flat_map(|l| l.pieces(arena)) {
+                advance(&mut mappings, &mut column, left);
+            }
+            if let Some(edited) = chunk.edited(arena) {
+                // Edited content is mapped as a whole to the start of what it replaced.
+                if !edited.is_empty() {
+                    let (line, original_column) = index.locate_utf16(self.original, chunk.start);
+                    let name = if chunk.store_name {
+                        let name = &self.original[chunk.start..chunk.end];
+                        Some(match names.iter().position(|n| n == name) {
+                            Some(i) => i,
+                            None => {
+                                names.push(String::from(name));
+                                names.len() - 1
+                            }
+                        })
+                    } else {
+                        None
+                    };
+                    if let Some(segments) = mappings.last_mut() {
+                        segments.push(Segment {
+                            generated_column: column,
+                            original: Some(OriginalLocation {
+                                source: 0,
+                                line,
+                                column: original_column,
+                                name,
+                            }),
+                        });
+                    }
+                }
+                advance(&mut mappings, &mut column, edited);
+            } else if let Some(content) = chunk.content {
+                let (mut line, mut original_column) =
+                    index.locate_utf16(self.original, chunk.start);
+                let mut needs_segment = true;
+
+                for c in content.chars() {
+                    if needs_segment || options.hires {
+                        if let Some(segments) = mappings.last_mut() {
+                            segments.push(Segment {
+                                generated_column: column,
+                                original: Some(OriginalLocation {
+                                    source: 0,
+                                    line,
+                                    column: original_column,
+                                    name: None,
+                                }),
+                            });
+                        }
+                        needs_segment = false;
+                    }
+
+                    if c == '\n' {
+                        mappings.push(Vec::new());
+                        column = 0;
+                        line += 1;
+                        original_column = 0;
+                        needs_segment = true;
+                    } else {
+                        column += c.len_utf16();
+                        original_column += c.len_utf16();
+                    }
+                }
+            }
+            for right in chunk.right.iter().flat_map(|r| r.pieces(arena)) {
+                advance(&mut mappings, &mut column, right);
+            }
+        }
+
+        let map = SourceMap {
+            file: options.file.clone(),
+            sources: vec![options.source.clone().unwrap_or_default()],
+            sources_content: if options.include_content {
+                vec![Some(String::from(self.original))]
+            } else {
+                Vec::new()
+            },
+            names,
+            mappings,
+        };
+
+        match self.input_map {
+            Some(ref input) => compose(&[input.clone(), map]),
+            None => Ok(map),
         }
-        s
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), Error> {
+        if index > self.original.len() {
+            Err(Error::IndexOutOfBound {
+                index,
+                length: self.original.len(),
+            })
+        } else if !self.original.is_char_boundary(index) {
+            Err(Error::NotACharBoundary { index })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_range(&self, start: usize, end: usize) -> Result<(), Error> {
+        if start > end || end > self.original.len() {
+            return Err(Error::InvalidRange {
+                start,
+                end,
+                length: self.original.len(),
+            });
+        }
+        self.check_index(start)?;
+        self.check_index(end)
     }
 
     #[inline]
@@ -40,21 +420,12 @@ impl<'a> RefactoryString<'a> {
         left: bool,
         append: bool,
     ) -> Result<(), Error> {
-        let (l, r) = self.chunks.split(index)?;
-
-        if append {
-            if left {
-                l.append_right(content)
-            } else {
-                r.append_left(content)
-            }
-        } else {
-            if left {
-                l.prepend_right(content)
-            } else {
-                r.prepend_left(content)
-            }
-        }
+        self.check_index(index)?;
+        let content = match self.normalize_line_endings {
+            Some(ending) => ending.normalize(content),
+            None => Cow::Borrowed(content),
+        };
+        self.chunks.insert(index, &content, left, append)
     }
 
     /// Append the content to the left of the index.
@@ -74,7 +445,50 @@ impl<'a> RefactoryString<'a> {
 
     /// Prepend the content to the right of the index.
     pub fn prepend_right(&mut self, index: usize, content: &str) -> Result<(), Error> {
-        self.do_insert(index, content, false,false)
+        self.do_insert(index, content, false, false)
+    }
+
+    /// Insert lines of text before the line containing the index. Every inserted line is
+    /// indented like the line containing the index, and ends with the dominant line ending
+    /// of the original content.
+    pub fn insert_line_before(&mut self, index: usize, content: &str) -> Result<(), Error> {
+        self.check_index(index)?;
+        let line_start = self.original[..index].rfind('\n').map_or(0, |i| i + 1);
+        let ending = self.line_ending().as_str();
+
+        let mut lines = indent_lines(content, self.indentation_at(line_start), ending);
+        lines.push_str(ending);
+        self.append_right(line_start, &lines)
+    }
+
+    /// Insert lines of text after the line containing the index. Every inserted line is
+    /// indented like the line containing the index, and ends with the dominant line ending
+    /// of the original content.
+    pub fn insert_line_after(&mut self, index: usize, content: &str) -> Result<(), Error> {
+        self.check_index(index)?;
+        let line_start = self.original[..index].rfind('\n').map_or(0, |i| i + 1);
+        let ending = self.line_ending().as_str();
+        let lines = indent_lines(content, self.indentation_at(line_start), ending);
+
+        match self.original[index..].find('\n') {
+            Some(i) => {
+                let mut lines = lines;
+                lines.push_str(ending);
+                self.append_left(index + i + 1, &lines)
+            }
+            None => {
+                let mut last_line = String::from(ending);
+                last_line.push_str(&lines);
+                self.append_left(self.original.len(), &last_line)
+            }
+        }
+    }
+
+    /// The whitespace at the start of the original line starting at `line_start`.
+    fn indentation_at(&self, line_start: usize) -> &'a str {
+        let line = &self.original[line_start..];
+        let len = line.len() - line.trim_start_matches([' ', '\t']).len();
+        &line[..len]
     }
 
     /// Prepend the content to the whole RefactoryString.
@@ -89,13 +503,143 @@ impl<'a> RefactoryString<'a> {
 
     /// Overwrite the content at the indices [start, end].
     pub fn overwrite(&mut self, start: usize, end: usize, content: &str) -> Result<(), Error> {
-        self.remove(start, end)?;
-        self.append_left(start, content)?;
-        Ok(())
+        self.check_range(start, end)?;
+        let content = match self.normalize_line_endings {
+            Some(ending) => ending.normalize(content),
+            None => Cow::Borrowed(content),
+        };
+        self.chunks.overwrite(start, end, &content)
+    }
+
+    /// Overwrite the content between two indices, with options. Unlike `overwrite`, the
+    /// original content is edited in place, so the new content is mapped to it in source
+    /// maps. The range cannot be empty.
+    pub fn overwrite_with(
+        &mut self,
+        start: usize,
+        end: usize,
+        content: &str,
+        options: &OverwriteOptions,
+    ) -> Result<(), Error> {
+        self.check_range(start, end)?;
+        if start == end {
+            return Err(Error::InvalidRange {
+                start,
+                end,
+                length: self.original.len(),
+            });
+        }
+        let content = match self.normalize_line_endings {
+            Some(ending) => ending.normalize(content),
+            None => Cow::Borrowed(content),
+        };
+        self.chunks.edit(
+            start,
+            end,
+            &content,
+            options.content_only,
+            options.store_name,
+        )
+    }
+
+    /// Restore the original content between two indices. Edits, overwrites and removals of
+    /// the range are undone, and content inserted inside of it or attached to its
+    /// boundaries (with `append_right(start)` or `append_left(end)`, for example) is
+    /// removed. Other changes are kept.
+    ///
+    /// Content edited in place with `overwrite_with` cannot be partially reset; resetting
+    /// only a part of its range returns `Error::CannotSplitEdit`.
+    pub fn reset(&mut self, start: usize, end: usize) -> Result<(), Error> {
+        self.check_range(start, end)?;
+        self.chunks.reset(start, end)
+    }
+
+    /// Merge the chunks of the original content that have no change, and free the memory
+    /// used by content that was replaced or removed. Chunks are only added when splitting
+    /// the original content, so long editing sessions should compact regularly.
+    pub fn compact(&mut self) {
+        self.chunks.compact();
+    }
+
+    /// Statistics about the chunks used to track changes.
+    pub fn chunk_stats(&self) -> ChunkStats {
+        self.chunks.stats()
     }
 
     /// Remove the content between two indices.
     pub fn remove(&mut self, start: usize, end: usize) -> Result<(), Error> {
+        self.check_range(start, end)?;
         self.chunks.remove(start, end)
     }
 }
+
+/// Push content inserted at an index, merging it with the insertion before it at the same
+/// index. Content inserted by `overwrite` starts a new edit, which then takes everything
+/// up to the removal it replaces.
+fn push_insertion(
+    edits: &mut Vec<Edit>,
+    overwriting: &mut bool,
+    index: usize,
+    piece: &str,
+    overwrite: bool,
+) {
+    if piece.is_empty() {
+        return;
+    }
+    match edits.last_mut() {
+        Some(last)
+            if last.is_insertion() && last.start == index && (*overwriting || !overwrite) =>
+        {
+            last.content.push_str(piece);
+        }
+        _ => {
+            edits.push(Edit {
+                start: index,
+                end: index,
+                content: String::from(piece),
+            });
+            *overwriting = false;
+        }
+    }
+    *overwriting |= overwrite;
+}
+
+/// Indent every non-empty line of a content, and join them with the line ending.
+fn indent_lines(content: &str, indentation: &str, ending: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    for (i, line) in content.lines().enumerate() {
+        if i > 0 {
+            result.push_str(ending);
+        }
+        if !line.is_empty() {
+            result.push_str(indentation);
+            result.push_str(line);
+        }
+    }
+    result
+}
+
+/// Move the generated position past inserted content, which has no mapping.
+fn advance(mappings: &mut Vec<Vec<Segment>>, column: &mut usize, inserted: &str) {
+    for c in inserted.chars() {
+        if c == '\n' {
+            mappings.push(Vec::new());
+            *column = 0;
+        } else {
+            *column += c.len_utf16();
+        }
+    }
+}
+
+impl<'a> fmt::Display for RefactoryString<'a> {
+    /// Serialize the changes to a string.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arena = self.chunks.arena();
+        for chunk in self.chunks.iter() {
+            for piece in chunk.pieces(arena) {
+                f.write_str(piece)?;
+            }
+        }
+        Ok(())
+    }
+}
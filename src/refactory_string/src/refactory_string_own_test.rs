@@ -4,6 +4,7 @@
 #![cfg(test)]
 use crate::error::Error;
 use crate::RefactoryString;
+use alloc::string::ToString;
 
 #[test]
 fn append() -> Result<(), Error> {
@@ -1,12 +1,13 @@
 #![cfg(test)]
 use crate::error::Error;
 use crate::RefactoryString;
+use alloc::string::ToString;
 
 #[test]
 fn basic() -> Result<(), Error> {
     //                   01234567890
     let content = "Hello World";
-    let mut buffer = RefactoryString::new(&content);
+    let mut buffer = RefactoryString::new(content);
 
     buffer.append_right(6, "Beautiful ")?;
     assert_eq!(&buffer.to_string(), "Hello Beautiful World");
@@ -0,0 +1,82 @@
+#![cfg(test)]
+use crate::error::Error;
+use crate::RefactoryString;
+use alloc::string::ToString;
+
+#[test]
+fn reset() -> Result<(), Error> {
+    let mut s = RefactoryString::new("0123456789");
+    s.prepend(">")?;
+    s.overwrite(1, 3, "ab")?;
+    s.remove(4, 6)?;
+    s.append_left(5, "x")?;
+    s.append_right(7, "y")?;
+    s.append_left(8, "z")?;
+    s.overwrite(8, 9, "!")?;
+    assert_eq!(&s.to_string(), ">0ab36y7z!9");
+
+    // Content appended to the left of 8 belongs to the range, but not the content
+    // overwriting 8..9.
+    s.reset(3, 8)?;
+    assert_eq!(&s.to_string(), ">0ab34567!9");
+
+    s.reset(0, 10)?;
+    assert_eq!(&s.to_string(), "0123456789");
+    assert!(!s.has_changed());
+
+    assert_eq!(
+        s.reset(3, 11),
+        Err(Error::InvalidRange {
+            start: 3,
+            end: 11,
+            length: 10
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn reset_overwrite() -> Result<(), Error> {
+    let mut s = RefactoryString::new("0123456789");
+    s.append_left(1, "<")?;
+    s.overwrite(1, 3, "ab")?;
+    s.append_left(1, ">")?;
+    assert_eq!(&s.to_string(), "0<ab>3456789");
+
+    s.reset(1, 3)?;
+    assert_eq!(&s.to_string(), "0<>123456789");
+
+    s.overwrite(1, 3, "ab")?;
+    s.compact();
+    s.reset(1, 3)?;
+    assert_eq!(&s.to_string(), "0<>123456789");
+
+    s.overwrite(1, 3, "ab")?;
+    s.overwrite(4, 5, "d")?;
+    s.reset(0, 4)?;
+    assert_eq!(&s.to_string(), "0123d56789");
+    Ok(())
+}
+
+#[test]
+fn reset_edit() -> Result<(), Error> {
+    let mut s = RefactoryString::new("a\r\nb\r\n");
+    s.convert_line_endings(crate::LineEnding::Lf)?;
+    assert_eq!(&s.to_string(), "a\nb\n");
+
+    s.reset(1, 3)?;
+    assert_eq!(&s.to_string(), "a\r\nb\n");
+
+    // Edits made with `overwrite_with` cannot be partially reset.
+    s.overwrite_with(0, 3, "c", &Default::default())?;
+    assert_eq!(
+        s.reset(0, 1),
+        Err(Error::CannotSplitEdit {
+            index: 1,
+            start: 0,
+            end: 3
+        })
+    );
+    assert_eq!(&s.to_string(), "cb\n");
+    Ok(())
+}
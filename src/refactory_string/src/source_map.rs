@@ -0,0 +1,357 @@
+use crate::error::Error;
+use crate::vlq;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Options used when generating a source map out of a RefactoryString.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMapOptions {
+    /// The filename of the generated output, stored in the `file` field of the map.
+    pub file: Option<String>,
+
+    /// The filename of the original content, stored in the `sources` field of the map.
+    pub source: Option<String>,
+
+    /// Whether to include the original content in the `sourcesContent` field of the map.
+    pub include_content: bool,
+
+    /// Generate a mapping for every character of the original content, instead of one
+    /// per chunk and line.
+    pub hires: bool,
+}
+
+/// A location in one of the sources of a map. All indices are 0-based, and columns are
+/// counted in UTF-16 code units, like every source map consumer expects.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OriginalLocation {
+    /// Index in the `sources` of the map.
+    pub source: usize,
+    pub line: usize,
+    pub column: usize,
+    /// Index in the `names` of the map, if any.
+    pub name: Option<usize>,
+}
+
+/// A single decoded mapping segment. Segments without an original location mark the
+/// start of generated content that does not come from any source.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Segment {
+    pub generated_column: usize,
+    pub original: Option<OriginalLocation>,
+}
+
+/// The result of looking up a generated position in a SourceMap.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OriginalPosition<'m> {
+    pub source: &'m str,
+    pub line: usize,
+    pub column: usize,
+    pub name: Option<&'m str>,
+}
+
+/// A source map (revision 3), with its mappings decoded. Each entry of `mappings` is a
+/// line of the generated content, containing its segments sorted by generated column.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SourceMap {
+    pub file: Option<String>,
+    pub sources: Vec<String>,
+    pub sources_content: Vec<Option<String>>,
+    pub names: Vec<String>,
+    pub mappings: Vec<Vec<Segment>>,
+}
+
+impl SourceMap {
+    /// Look up the original position of a generated line and column (both 0-based). This
+    /// returns the closest segment at or before the column, or None if that segment is
+    /// not mapped to any source.
+    pub fn original_position_for(
+        &self,
+        line: usize,
+        column: usize,
+    ) -> Option<OriginalPosition<'_>> {
+        let location = self.original_location_for(line, column)?;
+
+        Some(OriginalPosition {
+            source: self.sources.get(location.source)?,
+            line: location.line,
+            column: location.column,
+            name: location
+                .name
+                .and_then(|n| self.names.get(n).map(String::as_str)),
+        })
+    }
+
+    fn original_location_for(&self, line: usize, column: usize) -> Option<OriginalLocation> {
+        let segments = self.mappings.get(line)?;
+        let index = segments.partition_point(|s| s.generated_column <= column);
+        if index == 0 {
+            None
+        } else {
+            segments[index - 1].original
+        }
+    }
+
+    /// Encode the mappings to their VLQ representation.
+    pub fn encoded_mappings(&self) -> String {
+        encode_mappings(&self.mappings)
+    }
+
+    /// Serialize the source map to its JSON representation.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from(r#"{"version":3"#);
+        if let Some(ref file) = self.file {
+            json.push_str(r#","file":"#);
+            push_json_string(&mut json, file);
+        }
+        json.push_str(r#","sources":["#);
+        for (i, source) in self.sources.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            push_json_string(&mut json, source);
+        }
+        json.push(']');
+        if !self.sources_content.is_empty() {
+            json.push_str(r#","sourcesContent":["#);
+            for (i, content) in self.sources_content.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                match content {
+                    Some(c) => push_json_string(&mut json, c),
+                    None => json.push_str("null"),
+                }
+            }
+            json.push(']');
+        }
+        json.push_str(r#","names":["#);
+        for (i, name) in self.names.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            push_json_string(&mut json, name);
+        }
+        json.push_str(r#"],"mappings":""#);
+        json.push_str(&self.encoded_mappings());
+        json.push_str(r#""}"#);
+        json
+    }
+}
+
+fn push_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str(r#"\""#),
+            '\\' => out.push_str(r"\\"),
+            '\n' => out.push_str(r"\n"),
+            '\r' => out.push_str(r"\r"),
+            '\t' => out.push_str(r"\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, r"\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Decode the VLQ `mappings` field of a source map.
+pub fn decode_mappings(mappings: &str) -> Result<Vec<Vec<Segment>>, Error> {
+    let input = mappings.as_bytes();
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    let (mut source, mut original_line, mut original_column, mut name) = (0i64, 0i64, 0i64, 0i64);
+    let mut generated_column = 0i64;
+    let mut pos = 0;
+
+    while pos < input.len() {
+        match input[pos] {
+            b';' => {
+                lines.push(core::mem::take(&mut line));
+                generated_column = 0;
+                pos += 1;
+            }
+            b',' => {
+                pos += 1;
+            }
+            _ => {
+                let start = pos;
+                let mut fields = [0i64; 5];
+                let mut len = 0;
+                while pos < input.len() && input[pos] != b',' && input[pos] != b';' {
+                    if len == fields.len() {
+                        return Err(Error::InvalidMappings { position: start });
+                    }
+                    fields[len] = vlq::decode(input, &mut pos)?;
+                    len += 1;
+                }
+
+                generated_column += fields[0];
+                let original = match len {
+                    1 => None,
+                    4 | 5 => {
+                        source += fields[1];
+                        original_line += fields[2];
+                        original_column += fields[3];
+                        if len == 5 {
+                            name += fields[4];
+                        }
+                        if source < 0 || original_line < 0 || original_column < 0 || name < 0 {
+                            return Err(Error::InvalidMappings { position: start });
+                        }
+                        Some(OriginalLocation {
+                            source: source as usize,
+                            line: original_line as usize,
+                            column: original_column as usize,
+                            name: if len == 5 { Some(name as usize) } else { None },
+                        })
+                    }
+                    _ => return Err(Error::InvalidMappings { position: start }),
+                };
+                if generated_column < 0 {
+                    return Err(Error::InvalidMappings { position: start });
+                }
+
+                line.push(Segment {
+                    generated_column: generated_column as usize,
+                    original,
+                });
+            }
+        }
+    }
+    lines.push(line);
+
+    Ok(lines)
+}
+
+/// Encode decoded mappings into the VLQ `mappings` field of a source map.
+pub fn encode_mappings(lines: &[Vec<Segment>]) -> String {
+    let mut out = String::new();
+    let (mut source, mut original_line, mut original_column, mut name) = (0i64, 0i64, 0i64, 0i64);
+
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            out.push(';');
+        }
+        let mut generated_column = 0i64;
+        for (j, segment) in line.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            vlq::encode(segment.generated_column as i64 - generated_column, &mut out);
+            generated_column = segment.generated_column as i64;
+
+            if let Some(ref original) = segment.original {
+                vlq::encode(original.source as i64 - source, &mut out);
+                vlq::encode(original.line as i64 - original_line, &mut out);
+                vlq::encode(original.column as i64 - original_column, &mut out);
+                source = original.source as i64;
+                original_line = original.line as i64;
+                original_column = original.column as i64;
+
+                if let Some(n) = original.name {
+                    vlq::encode(n as i64 - name, &mut out);
+                    name = n as i64;
+                }
+            }
+        }
+    }
+
+    out
+}
+
+/// Compose a chain of source maps into a single one. Each map must describe a
+/// transformation of the output of the previous map in the list; the resulting map
+/// points the output of the last map back to the sources of the first one.
+pub fn compose(maps: &[SourceMap]) -> Result<SourceMap, Error> {
+    let mut it = maps.iter();
+    let first = match it.next() {
+        Some(m) => m.clone(),
+        None => return Ok(SourceMap::default()),
+    };
+
+    it.try_fold(first, |inner, outer| compose_pair(&inner, outer))
+}
+
+fn get_indexed(list: &[String], index: usize) -> Result<&String, Error> {
+    list.get(index).ok_or(Error::SourceMapIndexOutOfBound {
+        index,
+        length: list.len(),
+    })
+}
+
+fn compose_pair(inner: &SourceMap, outer: &SourceMap) -> Result<SourceMap, Error> {
+    let mut result = SourceMap {
+        file: outer.file.clone(),
+        ..SourceMap::default()
+    };
+    let mut source_indices = BTreeMap::new();
+    let mut name_indices = BTreeMap::new();
+
+    for line in &outer.mappings {
+        let mut segments: Vec<Segment> = Vec::with_capacity(line.len());
+
+        for segment in line {
+            let traced = match segment.original {
+                Some(ref location) => inner
+                    .original_location_for(location.line, location.column)
+                    .map(|traced| (traced, location.name)),
+                None => None,
+            };
+
+            let original = match traced {
+                Some((traced, outer_name)) => {
+                    let source = get_indexed(&inner.sources, traced.source)?;
+                    let source_index = *source_indices.entry(source).or_insert_with(|| {
+                        result.sources.push(source.clone());
+                        result
+                            .sources_content
+                            .push(inner.sources_content.get(traced.source).cloned().flatten());
+                        result.sources.len() - 1
+                    });
+
+                    // Prefer the name closest to the original source.
+                    let name = match (traced.name, outer_name) {
+                        (Some(n), _) => Some(get_indexed(&inner.names, n)?),
+                        (None, Some(n)) => Some(get_indexed(&outer.names, n)?),
+                        (None, None) => None,
+                    };
+                    let name_index = name.map(|name| {
+                        *name_indices.entry(name).or_insert_with(|| {
+                            result.names.push(name.clone());
+                            result.names.len() - 1
+                        })
+                    });
+
+                    Some(OriginalLocation {
+                        source: source_index,
+                        line: traced.line,
+                        column: traced.column,
+                        name: name_index,
+                    })
+                }
+                None => None,
+            };
+
+            // Consecutive unmapped segments are redundant.
+            if original.is_none() && segments.last().is_none_or(|s| s.original.is_none()) {
+                continue;
+            }
+            segments.push(Segment {
+                generated_column: segment.generated_column,
+                original,
+            });
+        }
+
+        result.mappings.push(segments);
+    }
+
+    if result.sources_content.iter().all(Option::is_none) {
+        result.sources_content.clear();
+    }
+
+    Ok(result)
+}
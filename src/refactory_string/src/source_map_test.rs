@@ -0,0 +1,137 @@
+#![cfg(test)]
+use crate::error::Error;
+use crate::source_map::{
+    compose, decode_mappings, encode_mappings, OriginalPosition, SourceMapOptions,
+};
+use crate::RefactoryString;
+use alloc::string::{String, ToString};
+
+#[test]
+fn vlq_round_trip() -> Result<(), Error> {
+    let mappings = "AAAA,SAASA,gBAAgB;;AACA,mCAAmC;AACnD";
+    let decoded = decode_mappings(mappings)?;
+    assert_eq!(decoded.len(), 4);
+    assert_eq!(decoded[1].len(), 0);
+    assert_eq!(encode_mappings(&decoded), mappings);
+
+    assert!(decode_mappings("AA!A").is_err());
+    assert!(decode_mappings("AAAAAA").is_err());
+    Ok(())
+}
+
+#[test]
+fn generate_map() -> Result<(), Error> {
+    let mut s = RefactoryString::new("problems = 99");
+    s.overwrite(0, 8, "answer")?;
+    s.overwrite(11, 13, "42")?;
+
+    let map = s.generate_map(&SourceMapOptions {
+        file: Some("output.js".to_string()),
+        source: Some("input.js".to_string()),
+        include_content: true,
+        hires: false,
+    })?;
+
+    assert_eq!(map.encoded_mappings(), "MAAQ");
+    assert_eq!(
+        map.to_json(),
+        r#"{"version":3,"file":"output.js","sources":["input.js"],"sourcesContent":["problems = 99"],"names":[],"mappings":"MAAQ"}"#
+    );
+    assert_eq!(
+        map.original_position_for(0, 7),
+        Some(OriginalPosition {
+            source: "input.js",
+            line: 0,
+            column: 8,
+            name: None
+        })
+    );
+    assert_eq!(map.original_position_for(0, 2), None);
+
+    Ok(())
+}
+
+#[test]
+fn generate_map_multiline() -> Result<(), Error> {
+    let mut s = RefactoryString::new("a\nbc\nd");
+    s.prepend("// header\n")?;
+    s.remove(2, 3)?;
+
+    assert_eq!(&s.to_string(), "// header\na\nc\nd");
+    let map = s.generate_map(&SourceMapOptions::default())?;
+    assert_eq!(map.encoded_mappings(), ";AAAA;AACC;AACD");
+
+    let hires = s.generate_map(&SourceMapOptions {
+        hires: true,
+        ..SourceMapOptions::default()
+    })?;
+    assert_eq!(hires.encoded_mappings(), ";AAAA,CAAC;AACA,CAAC;AACF");
+    Ok(())
+}
+
+#[test]
+fn compose_chain() -> Result<(), Error> {
+    let original = "let i = 1;\nlet j = i;";
+
+    let mut first = RefactoryString::new(original);
+    first.overwrite(4, 5, "index")?;
+    first.overwrite(19, 20, "index")?;
+    let first_output = first.to_string();
+    let first_map = first.generate_map(&SourceMapOptions {
+        source: Some("original.rs".to_string()),
+        hires: true,
+        ..SourceMapOptions::default()
+    })?;
+
+    let mut second = RefactoryString::new(&first_output);
+    second.prepend("// generated\n")?;
+    let second_output = second.to_string();
+    let second_map = second.generate_map(&SourceMapOptions {
+        source: Some("first.rs".to_string()),
+        hires: true,
+        ..SourceMapOptions::default()
+    })?;
+    assert_eq!(
+        &second_output,
+        "// generated\nlet index = 1;\nlet j = index;"
+    );
+
+    let composed = compose(&[first_map.clone(), second_map.clone()])?;
+    assert_eq!(composed.sources, ["original.rs"]);
+
+    // `j` on the last line.
+    assert_eq!(
+        composed.original_position_for(2, 4),
+        Some(OriginalPosition {
+            source: "original.rs",
+            line: 1,
+            column: 4,
+            name: None
+        })
+    );
+    // `= 1` on the second line.
+    assert_eq!(
+        composed.original_position_for(1, 10),
+        Some(OriginalPosition {
+            source: "original.rs",
+            line: 0,
+            column: 6,
+            name: None
+        })
+    );
+    assert_eq!(composed.original_position_for(0, 3), None);
+
+    // Using an input map composes automatically.
+    let mut second = RefactoryString::new(&first_output);
+    second.set_input_map(first_map);
+    second.prepend("// generated\n")?;
+    let auto = second.generate_map(&SourceMapOptions {
+        hires: true,
+        ..SourceMapOptions::default()
+    })?;
+    assert_eq!(auto.mappings, composed.mappings);
+    assert_eq!(auto.sources, composed.sources);
+
+    let _: String = auto.to_json();
+    Ok(())
+}
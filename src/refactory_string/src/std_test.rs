@@ -0,0 +1,30 @@
+#![cfg(all(test, feature = "std"))]
+use crate::error::Error;
+use crate::RefactoryString;
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+#[test]
+fn write_to() -> Result<(), Box<dyn std::error::Error>> {
+    let mut s = RefactoryString::new("problems = 99");
+    s.overwrite(0, 8, "answer")?;
+    s.append(";")?;
+
+    let mut out = Vec::new();
+    s.write_to(&mut out)?;
+    assert_eq!(out, b"answer = 99;");
+    Ok(())
+}
+
+#[test]
+fn std_error() {
+    let err: Box<dyn std::error::Error> = Box::new(Error::IndexOutOfBound {
+        index: 5,
+        length: 4,
+    });
+    assert_eq!(
+        err.to_string(),
+        "index 5 is out of bound of the original content (length 4)"
+    );
+}
@@ -0,0 +1,88 @@
+#![cfg(all(test, feature = "tree-sitter"))]
+use crate::error::Error;
+use crate::RefactoryString;
+use alloc::string::ToString;
+use tree_sitter::{Node, Parser, Tree};
+
+fn parse(content: &str, old_tree: Option<&Tree>) -> Tree {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_json::LANGUAGE.into())
+        .unwrap();
+    parser.parse(content, old_tree).unwrap()
+}
+
+fn find<'t>(node: Node<'t>, content: &str, kind: &str, text: &str) -> Option<Node<'t>> {
+    if node.kind() == kind && &content[node.byte_range()] == text {
+        return Some(node);
+    }
+    let mut cursor = node.walk();
+    let found = node
+        .children(&mut cursor)
+        .find_map(|child| find(child, content, kind, text));
+    found
+}
+
+#[test]
+fn edit_nodes() -> Result<(), Error> {
+    let content = "{\n  \"a\": 1,\n  \"b\": [2, 3],\n  \"c\": null\n}\n";
+    let tree = parse(content, None);
+    let root = tree.root_node();
+
+    let mut s = RefactoryString::new(content);
+    s.overwrite_node(&find(root, content, "number", "1").unwrap(), "\"one\"")?;
+    s.wrap_node(&find(root, content, "array", "[2, 3]").unwrap(), "[", "]")?;
+    s.wrap_node(
+        &find(root, content, "array", "[2, 3]").unwrap(),
+        "[",
+        ", 4]",
+    )?;
+    let pair = find(root, content, "pair", "\"c\": null").unwrap();
+    s.remove_node(&pair.prev_sibling().unwrap())?;
+    s.remove_node(&pair)?;
+    assert_eq!(
+        &s.to_string(),
+        "{\n  \"a\": \"one\",\n  \"b\": [[[2, 3]], 4]\n  \n}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn incremental_parse() -> Result<(), Error> {
+    let content = "{\n  \"a\": 1,\n  \"b\": [2, 3]\n}\n";
+    let mut tree = parse(content, None);
+    let root = tree.root_node();
+
+    let mut s = RefactoryString::new(content);
+    s.overwrite_node(
+        &find(root, content, "number", "1").unwrap(),
+        "{\n    \"x\": true\n  }",
+    )?;
+    s.append_left(content.find(']').unwrap(), ", 4")?;
+
+    let edits = s.input_edits();
+    assert_eq!(edits.len(), 2);
+    assert!(edits[0].start_byte > edits[1].start_byte);
+    assert_eq!(
+        edits[1].start_position,
+        tree_sitter::Point { row: 1, column: 7 }
+    );
+    assert_eq!(
+        edits[1].new_end_position,
+        tree_sitter::Point { row: 3, column: 3 }
+    );
+
+    for edit in &edits {
+        tree.edit(edit);
+    }
+    let output = s.to_string();
+    let reparsed = parse(&output, Some(&tree));
+    let fresh = parse(&output, None);
+    assert!(!reparsed.root_node().has_error());
+    assert_eq!(reparsed.root_node().to_sexp(), fresh.root_node().to_sexp());
+    assert_eq!(
+        reparsed.root_node().end_byte(),
+        fresh.root_node().end_byte()
+    );
+    Ok(())
+}
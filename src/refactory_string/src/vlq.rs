@@ -0,0 +1,68 @@
+//! Base64 VLQ encoding as used by the `mappings` field of source maps (revision 3).
+use crate::error::Error;
+use alloc::string::String;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const CONTINUATION_BIT: u8 = 0b10_0000;
+const VALUE_MASK: u8 = 0b01_1111;
+
+fn decode_base64(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// Append the VLQ representation of `value` to `out`.
+pub(crate) fn encode(value: i64, out: &mut String) {
+    let mut vlq = if value < 0 {
+        ((-value as u64) << 1) | 1
+    } else {
+        (value as u64) << 1
+    };
+
+    loop {
+        let mut digit = (vlq & VALUE_MASK as u64) as u8;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= CONTINUATION_BIT;
+        }
+        out.push(BASE64[digit as usize] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+/// Decode a single VLQ value from `input`, starting at `*pos`. On success, `*pos` is moved
+/// past the value.
+pub(crate) fn decode(input: &[u8], pos: &mut usize) -> Result<i64, Error> {
+    let start = *pos;
+    let mut result: u64 = 0;
+    let mut shift = 0;
+
+    loop {
+        let digit = input
+            .get(*pos)
+            .and_then(|c| decode_base64(*c))
+            .ok_or(Error::InvalidMappings { position: *pos })?;
+        *pos += 1;
+
+        if shift > 60 {
+            return Err(Error::InvalidMappings { position: start });
+        }
+        result |= ((digit & VALUE_MASK) as u64) << shift;
+        shift += 5;
+
+        if digit & CONTINUATION_BIT == 0 {
+            break;
+        }
+    }
+
+    let value = (result >> 1) as i64;
+    Ok(if result & 1 == 1 { -value } else { value })
+}
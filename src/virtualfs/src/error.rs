@@ -1,37 +1,94 @@
-use thiserror::Error;
+use crate::path::{OwnedPath, Path};
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt;
 
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
 
 /// Generic error type for any errors happening in the virtualfs crate.
-#[derive(Error, Debug)]
+#[derive(Debug)]
 pub enum Error {
     /// Error coming from IO operations.
-    #[error("an IO error happened: {0:?}")]
-    Io(#[from] IoError),
+    Io(IoError),
 
-    #[error("Custom error: {0}")]
-    Custom(#[from] Box<dyn std::error::Error + Send + Sync>),
+    /// Error coming from an operation on a path of a FileSystem. Every error of the
+    /// FileSystem backends uses this variant.
+    Path {
+        path: OwnedPath,
+        kind: IoError,
+    },
+
+    Custom(Box<dyn core::error::Error + Send + Sync>),
 
     /// Represents any error that did not have an actual type attached to it.
     /// For example, custom implementations can use this type to provide
     /// information.
-    #[error(r#"Custom error string: "{0}""#)]
     String(String),
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(io) => write!(f, "an IO error happened: {:?}", io),
+            Error::Path { path, kind } => write!(f, "{} ({})", kind, path.as_str()),
+            Error::Custom(err) => write!(f, "Custom error: {}", err),
+            Error::String(string) => write!(f, r#"Custom error string: "{}""#, string),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Error::Io(io) => Some(io),
+            Error::Custom(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<IoError> for Error {
+    fn from(err: IoError) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<Box<dyn core::error::Error + Send + Sync>> for Error {
+    fn from(err: Box<dyn core::error::Error + Send + Sync>) -> Self {
+        Error::Custom(err)
+    }
+}
+
 impl Error {
-    pub fn custom<E: std::error::Error + Send + Sync + 'static>(err: E) -> Self {
+    pub fn custom<E: core::error::Error + Send + Sync + 'static>(err: E) -> Self {
         Self::Custom(Box::new(err))
     }
 
+    /// An IO error on a path.
+    pub fn path<P: AsRef<Path>>(path: P, kind: IoError) -> Self {
+        Self::Path {
+            path: path.as_ref().to_owned(),
+            kind,
+        }
+    }
+
     pub fn get_io(&self) -> Option<&IoError> {
         match self {
             Error::Io(x) => Some(x),
+            Error::Path { kind, .. } => Some(kind),
             _ => None,
         }
     }
 
-    pub fn get_custom(&self) -> Option<&dyn std::error::Error> {
+    pub fn get_path(&self) -> Option<&Path> {
+        match self {
+            Error::Path { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    pub fn get_custom(&self) -> Option<&dyn core::error::Error> {
         match self {
             Error::Custom(x) => Some(x.as_ref()),
             _ => None,
@@ -46,142 +103,207 @@ impl Error {
     }
 }
 
-#[cfg(not(feature = "no_std"))]
-impl Into<std::io::Error> for Error {
-    fn into(self) -> std::io::Error {
-        match self {
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> std::io::Error {
+        match err {
             Error::Io(io) => io.into(),
-            x => std::io::Error::new(std::io::ErrorKind::Other, Box::new(x)),
+            Error::Path {
+                kind: IoError::Os(code),
+                ..
+            } => std::io::Error::from_raw_os_error(code),
+            Error::Path { kind, .. } => std::io::Error::new(kind.into(), err),
+            x => std::io::Error::other(Box::new(x)),
         }
     }
 }
 
-/// A symmetrical error enum to [`std::io::ErrorKind`]. Because we need to support
-/// [`no_std`], we cannot use [`std::io::Error`] directly. We thus employ this error
-/// enum, and have a From type to convert from and into a regular [`std::io::Error`]
-/// when available.
-#[derive(Clone, Copy, Debug, Error, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// A symmetrical error enum to `std::io::ErrorKind`. Because we need to support
+/// `no_std`, we cannot use `std::io::Error` directly. We thus employ this error
+/// enum, and have a From type to convert from and into a regular `std::io::Error`
+/// when the `std` feature is enabled.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 #[non_exhaustive]
 pub enum IoError {
-    #[error("An OS error occured, code {0}")]
     Os(i32),
-
-    #[error("An entity was not found, often a file.")]
     NotFound,
-
-    #[error("The operation lacked the necessary privileges to complete.")]
     PermissionDenied,
-
-    #[error("The connection was refused by the remote server.")]
     ConnectionRefused,
-
-    #[error("The connection was reset by the remote server.")]
     ConnectionReset,
-
-    #[error("The connection was aborted (terminated) by the remote server.")]
     ConnectionAborted,
-
-    #[error("The network operation failed because it was not connected yet.")]
     NotConnected,
-
-    #[error(
-        "A socket address could not be bound because the address is already in use elsewhere."
-    )]
     AddrInUse,
-
-    #[error("A nonexistent interface was requested or the requested address was not local.")]
     AddrNotAvailable,
-
-    #[error("The operation failed because a pipe was closed.")]
     BrokenPipe,
-
-    #[error("An entity already exists, often a file.")]
     AlreadyExists,
-
-    #[error("The operation needs to block to complete, but the blocking operation was requested to not occur.")]
     WouldBlock,
-
-    #[error("A parameter was incorrect.")]
     InvalidInput,
-
-    #[error("Data not valid for the operation were encountered.")]
     InvalidData,
-
-    #[error("The I/O operation's timeout expired, causing it to be canceled.")]
     TimedOut,
-
-    #[error("An error returned when an operation could not be completed because a call to write returned Ok(0).")]
     WriteZero,
-
-    #[error("This operation was interrupted.")]
     Interrupted,
-
-    #[error("Any I/O error not part of this list.")]
     Other,
-
-    #[error(r#"An error returned when an operation could not be completed because an"end of file" was reached prematurely."#)]
     UnexpectedEof,
+    NotADirectory,
+    IsADirectory,
+    DirectoryNotEmpty,
+    ReadOnlyFilesystem,
+    Unsupported,
+}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IoError::Os(code) => write!(f, "An OS error occured, code {}", code),
+            IoError::NotFound => f.write_str("An entity was not found, often a file."),
+            IoError::PermissionDenied => f.write_str("The operation lacked the necessary privileges to complete."),
+            IoError::ConnectionRefused => f.write_str("The connection was refused by the remote server."),
+            IoError::ConnectionReset => f.write_str("The connection was reset by the remote server."),
+            IoError::ConnectionAborted => f.write_str("The connection was aborted (terminated) by the remote server."),
+            IoError::NotConnected => f.write_str("The network operation failed because it was not connected yet."),
+            IoError::AddrInUse => f.write_str("A socket address could not be bound because the address is already in use elsewhere."),
+            IoError::AddrNotAvailable => f.write_str("A nonexistent interface was requested or the requested address was not local."),
+            IoError::BrokenPipe => f.write_str("The operation failed because a pipe was closed."),
+            IoError::AlreadyExists => f.write_str("An entity already exists, often a file."),
+            IoError::WouldBlock => f.write_str("The operation needs to block to complete, but the blocking operation was requested to not occur."),
+            IoError::InvalidInput => f.write_str("A parameter was incorrect."),
+            IoError::InvalidData => f.write_str("Data not valid for the operation were encountered."),
+            IoError::TimedOut => f.write_str("The I/O operation's timeout expired, causing it to be canceled."),
+            IoError::WriteZero => f.write_str("An error returned when an operation could not be completed because a call to write returned Ok(0)."),
+            IoError::Interrupted => f.write_str("This operation was interrupted."),
+            IoError::Other => f.write_str("Any I/O error not part of this list."),
+            IoError::UnexpectedEof => f.write_str(r#"An error returned when an operation could not be completed because an"end of file" was reached prematurely."#),
+            IoError::NotADirectory => f.write_str("A directory was expected, but the path points to something else."),
+            IoError::IsADirectory => f.write_str("A file was expected, but the path points to a directory."),
+            IoError::DirectoryNotEmpty => f.write_str("A directory was expected to be empty, but it has entries."),
+            IoError::ReadOnlyFilesystem => f.write_str("The file system is read-only."),
+            IoError::Unsupported => f.write_str("This operation is unsupported."),
+        }
+    }
+}
+
+impl core::error::Error for IoError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::ErrorKind> for IoError {
+    fn from(kind: std::io::ErrorKind) -> Self {
+        match kind {
+            std::io::ErrorKind::NotFound => IoError::NotFound,
+            std::io::ErrorKind::PermissionDenied => IoError::PermissionDenied,
+            std::io::ErrorKind::ConnectionRefused => IoError::ConnectionRefused,
+            std::io::ErrorKind::ConnectionReset => IoError::ConnectionReset,
+            std::io::ErrorKind::ConnectionAborted => IoError::ConnectionAborted,
+            std::io::ErrorKind::NotConnected => IoError::NotConnected,
+            std::io::ErrorKind::AddrInUse => IoError::AddrInUse,
+            std::io::ErrorKind::AddrNotAvailable => IoError::AddrNotAvailable,
+            std::io::ErrorKind::BrokenPipe => IoError::BrokenPipe,
+            std::io::ErrorKind::AlreadyExists => IoError::AlreadyExists,
+            std::io::ErrorKind::WouldBlock => IoError::WouldBlock,
+            std::io::ErrorKind::InvalidInput => IoError::InvalidInput,
+            std::io::ErrorKind::InvalidData => IoError::InvalidData,
+            std::io::ErrorKind::TimedOut => IoError::TimedOut,
+            std::io::ErrorKind::WriteZero => IoError::WriteZero,
+            std::io::ErrorKind::Interrupted => IoError::Interrupted,
+            std::io::ErrorKind::UnexpectedEof => IoError::UnexpectedEof,
+            std::io::ErrorKind::NotADirectory => IoError::NotADirectory,
+            std::io::ErrorKind::IsADirectory => IoError::IsADirectory,
+            std::io::ErrorKind::DirectoryNotEmpty => IoError::DirectoryNotEmpty,
+            std::io::ErrorKind::ReadOnlyFilesystem => IoError::ReadOnlyFilesystem,
+            std::io::ErrorKind::Unsupported => IoError::Unsupported,
+            _ => IoError::Other,
+        }
+    }
 }
 
-#[cfg(not(feature = "no_std"))]
+#[cfg(feature = "std")]
 impl From<std::io::Error> for IoError {
+    /// Map an error to its kind. OS errors without a matching kind keep their code.
     fn from(err: std::io::Error) -> Self {
-        if let Some(os_code) = err.raw_os_error() {
-            IoError::Os(os_code)
-        } else {
-            match err.kind() {
-                std::io::ErrorKind::NotFound => IoError::NotFound,
-                std::io::ErrorKind::PermissionDenied => IoError::PermissionDenied,
-                std::io::ErrorKind::ConnectionRefused => IoError::ConnectionRefused,
-                std::io::ErrorKind::ConnectionReset => IoError::ConnectionReset,
-                std::io::ErrorKind::ConnectionAborted => IoError::ConnectionAborted,
-                std::io::ErrorKind::NotConnected => IoError::NotConnected,
-                std::io::ErrorKind::AddrInUse => IoError::AddrInUse,
-                std::io::ErrorKind::AddrNotAvailable => IoError::AddrNotAvailable,
-                std::io::ErrorKind::BrokenPipe => IoError::BrokenPipe,
-                std::io::ErrorKind::AlreadyExists => IoError::AlreadyExists,
-                std::io::ErrorKind::WouldBlock => IoError::WouldBlock,
-                std::io::ErrorKind::InvalidInput => IoError::InvalidInput,
-                std::io::ErrorKind::InvalidData => IoError::InvalidData,
-                std::io::ErrorKind::TimedOut => IoError::TimedOut,
-                std::io::ErrorKind::WriteZero => IoError::WriteZero,
-                std::io::ErrorKind::Interrupted => IoError::Interrupted,
-                std::io::ErrorKind::Other => IoError::Other,
-                std::io::ErrorKind::UnexpectedEof => IoError::UnexpectedEof,
-                x => unreachable!("Unknown std::io::ErrorKind: {:?}", x),
-            }
+        match IoError::from(err.kind()) {
+            IoError::Other => err.raw_os_error().map_or(IoError::Other, IoError::Os),
+            kind => kind,
         }
     }
 }
 
-#[cfg(not(feature = "no_std"))]
-impl Into<std::io::Error> for IoError {
-    fn into(self) -> std::io::Error {
-        match self {
-            IoError::Os(code) => std::io::Error::from_raw_os_error(code),
+#[cfg(feature = "std")]
+impl From<IoError> for std::io::ErrorKind {
+    fn from(err: IoError) -> std::io::ErrorKind {
+        match err {
+            IoError::Os(code) => std::io::Error::from_raw_os_error(code).kind(),
 
-            IoError::NotFound => std::io::Error::from(std::io::ErrorKind::NotFound),
-            IoError::PermissionDenied => std::io::Error::from(std::io::ErrorKind::PermissionDenied),
-            IoError::ConnectionRefused => {
-                std::io::Error::from(std::io::ErrorKind::ConnectionRefused)
-            }
-            IoError::ConnectionReset => std::io::Error::from(std::io::ErrorKind::ConnectionReset),
-            IoError::ConnectionAborted => {
-                std::io::Error::from(std::io::ErrorKind::ConnectionAborted)
-            }
-            IoError::NotConnected => std::io::Error::from(std::io::ErrorKind::NotConnected),
-            IoError::AddrInUse => std::io::Error::from(std::io::ErrorKind::AddrInUse),
-            IoError::AddrNotAvailable => std::io::Error::from(std::io::ErrorKind::AddrNotAvailable),
-            IoError::BrokenPipe => std::io::Error::from(std::io::ErrorKind::BrokenPipe),
-            IoError::AlreadyExists => std::io::Error::from(std::io::ErrorKind::AlreadyExists),
-            IoError::WouldBlock => std::io::Error::from(std::io::ErrorKind::WouldBlock),
-            IoError::InvalidInput => std::io::Error::from(std::io::ErrorKind::InvalidInput),
-            IoError::InvalidData => std::io::Error::from(std::io::ErrorKind::InvalidData),
-            IoError::TimedOut => std::io::Error::from(std::io::ErrorKind::TimedOut),
-            IoError::WriteZero => std::io::Error::from(std::io::ErrorKind::WriteZero),
-            IoError::Interrupted => std::io::Error::from(std::io::ErrorKind::Interrupted),
-            IoError::Other => std::io::Error::from(std::io::ErrorKind::Other),
-            IoError::UnexpectedEof => std::io::Error::from(std::io::ErrorKind::UnexpectedEof),
+            IoError::NotFound => std::io::ErrorKind::NotFound,
+            IoError::PermissionDenied => std::io::ErrorKind::PermissionDenied,
+            IoError::ConnectionRefused => std::io::ErrorKind::ConnectionRefused,
+            IoError::ConnectionReset => std::io::ErrorKind::ConnectionReset,
+            IoError::ConnectionAborted => std::io::ErrorKind::ConnectionAborted,
+            IoError::NotConnected => std::io::ErrorKind::NotConnected,
+            IoError::AddrInUse => std::io::ErrorKind::AddrInUse,
+            IoError::AddrNotAvailable => std::io::ErrorKind::AddrNotAvailable,
+            IoError::BrokenPipe => std::io::ErrorKind::BrokenPipe,
+            IoError::AlreadyExists => std::io::ErrorKind::AlreadyExists,
+            IoError::WouldBlock => std::io::ErrorKind::WouldBlock,
+            IoError::InvalidInput => std::io::ErrorKind::InvalidInput,
+            IoError::InvalidData => std::io::ErrorKind::InvalidData,
+            IoError::TimedOut => std::io::ErrorKind::TimedOut,
+            IoError::WriteZero => std::io::ErrorKind::WriteZero,
+            IoError::Interrupted => std::io::ErrorKind::Interrupted,
+            IoError::Other => std::io::ErrorKind::Other,
+            IoError::UnexpectedEof => std::io::ErrorKind::UnexpectedEof,
+            IoError::NotADirectory => std::io::ErrorKind::NotADirectory,
+            IoError::IsADirectory => std::io::ErrorKind::IsADirectory,
+            IoError::DirectoryNotEmpty => std::io::ErrorKind::DirectoryNotEmpty,
+            IoError::ReadOnlyFilesystem => std::io::ErrorKind::ReadOnlyFilesystem,
+            IoError::Unsupported => std::io::ErrorKind::Unsupported,
         }
     }
 }
+
+#[cfg(feature = "std")]
+impl From<IoError> for std::io::Error {
+    fn from(err: IoError) -> std::io::Error {
+        match err {
+            IoError::Os(code) => std::io::Error::from_raw_os_error(code),
+            kind => std::io::Error::from(std::io::ErrorKind::from(kind)),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+
+    #[cfg(unix)]
+    #[test]
+    fn os_codes() {
+        // ENOENT and ENOTDIR.
+        assert_eq!(
+            IoError::from(std::io::Error::from_raw_os_error(2)),
+            IoError::NotFound
+        );
+        assert_eq!(
+            IoError::from(std::io::Error::from_raw_os_error(20)),
+            IoError::NotADirectory
+        );
+    }
+
+    #[test]
+    fn io_conversions() {
+        assert_eq!(
+            IoError::from(std::io::Error::other("custom")),
+            IoError::Other
+        );
+
+        let err = std::io::Error::from(Error::path("/a/b", IoError::IsADirectory));
+        assert_eq!(err.kind(), std::io::ErrorKind::IsADirectory);
+        assert_eq!(
+            err.to_string(),
+            "A file was expected, but the path points to a directory. (/a/b)"
+        );
+        assert_eq!(
+            std::io::Error::from(IoError::DirectoryNotEmpty).kind(),
+            std::io::ErrorKind::DirectoryNotEmpty
+        );
+    }
+}
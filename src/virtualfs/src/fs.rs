@@ -1,52 +1,127 @@
 use crate::error::Result;
-use crate::path::{OwnedPath, Path};
+use crate::path::Path;
+use alloc::vec::Vec;
 
+mod dynamic;
+#[cfg(feature = "std")]
+mod entry;
 mod memory;
+mod metadata;
+mod overlay;
+#[cfg(feature = "std")]
+mod physical;
+mod record;
+#[cfg(feature = "std")]
+mod shared;
+mod walk;
 
-pub struct Entry<'a, Fs: FileSystem + Sized> {
-    fs: &'a Fs,
-    offset: usize,
-    path: OwnedPath,
-}
-
-impl<'a, Fs: FileSystem> Entry<'a, Fs> {
-    pub fn new<P: AsRef<Path>>(fs: &'a Fs, path: P) -> Self {
-        Entry {
-            fs,
-            offset: 0,
-            path: path.as_ref().to_owned(),
-        }
-    }
-}
-
-impl<'a, Fs: FileSystem> std::io::Read for Entry<'a, Fs> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let bytes: &[u8] = self
-            .fs
-            .read(&self.path)
-            .map_err(std::convert::Into::<std::io::Error>::into)?;
-        let len = std::cmp::min(bytes.len(), buf.len());
-        buf[0..len].copy_from_slice(&bytes[0..len]);
-        self.offset += len;
-        Ok(len)
-    }
-}
-
-// pub struct Stat<'a, Fs: FileSystem + Sized> {
-//     fs: &'a Fs,
-//     path: OwnedPath,
-// }
+pub use dynamic::DynFileSystem;
+#[cfg(feature = "std")]
+pub use entry::{Entry, EntryMut, OpenOptions};
+pub use memory::MemoryFileSystem;
+#[cfg(feature = "std")]
+pub use metadata::SystemClock;
+pub use metadata::{content_hash, Clock, EpochClock, Metadata};
+pub use overlay::OverlayFileSystem;
+#[cfg(feature = "std")]
+pub use physical::PhysicalFileSystem;
+pub use record::{apply_actions, optimize_actions, Action, RecordingFileSystem};
+#[cfg(feature = "std")]
+pub use shared::SharedMemoryFileSystem;
+pub use walk::{DirEntry, EntryKind, Walk};
 
 pub trait FileSystem: Send + Sized {
     fn read<P: AsRef<Path>>(&self, path: P) -> Result<&[u8]>;
     fn exists<P: AsRef<Path>>(&self, path: P) -> bool;
-    // fn stat<P: AsRef<Path>>(&self, path: P) -> Result<Stat<'_, Self>>;
+    /// The entries of a directory, sorted by name.
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Vec<DirEntry>>;
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Metadata>;
 
     fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&mut self, path: P, content: C) -> Result<()>;
     fn delete<P: AsRef<Path>>(&mut self, path: P) -> Result<()>;
     fn rename<From: AsRef<Path>, To: AsRef<Path>>(&mut self, from: From, to: To) -> Result<()>;
 
+    #[cfg(feature = "std")]
+    /// A read-only handle to a file. To write to a file through a handle, use
+    /// [`OpenOptions`].
     fn entry<P: AsRef<Path>>(&self, path: P) -> Result<Entry<'_, Self>> {
         Ok(Entry::new(self, path))
     }
+
+    /// Iterate recursively over the entries under a directory.
+    fn walk<P: AsRef<Path>>(&self, path: P) -> Walk<'_, Self> {
+        Walk::new(self, path)
+    }
+}
+
+/// The errors every FileSystem returns, checked by the tests of each backend.
+#[cfg(test)]
+pub(crate) fn check_error_contract<Fs: FileSystem>(fs: &mut Fs) -> Result<()> {
+    use crate::error::IoError;
+    use alloc::borrow::ToOwned;
+    use alloc::string::String;
+
+    fn error<T>(result: Result<T>) -> Option<(IoError, String)> {
+        let err = result.err()?;
+        Some((*err.get_io()?, err.get_path()?.as_str().to_owned()))
+    }
+    let expected = |kind: IoError, path: &str| Some((kind, path.to_owned()));
+
+    fs.write("/dir/file.txt", "content")?;
+    fs.write("/dir/sub/a.txt", "a")?;
+
+    assert_eq!(
+        error(fs.read("/missing")),
+        expected(IoError::NotFound, "/missing")
+    );
+    assert_eq!(
+        error(fs.read("/dir")),
+        expected(IoError::IsADirectory, "/dir")
+    );
+    assert_eq!(
+        error(fs.read("/dir/file.txt/a")),
+        expected(IoError::NotADirectory, "/dir/file.txt/a")
+    );
+    assert_eq!(
+        error(fs.read_dir("/missing")),
+        expected(IoError::NotFound, "/missing")
+    );
+    assert_eq!(
+        error(fs.read_dir("/dir/file.txt")),
+        expected(IoError::NotADirectory, "/dir/file.txt")
+    );
+    assert_eq!(
+        error(fs.metadata("/missing")),
+        expected(IoError::NotFound, "/missing")
+    );
+    assert_eq!(
+        error(fs.write("/dir", "")),
+        expected(IoError::IsADirectory, "/dir")
+    );
+    assert_eq!(
+        error(fs.write("/dir/file.txt/a", "")),
+        expected(IoError::NotADirectory, "/dir/file.txt/a")
+    );
+    assert_eq!(
+        error(fs.delete("/missing")),
+        expected(IoError::NotFound, "/missing")
+    );
+    assert_eq!(
+        error(fs.rename("/missing", "/other")),
+        expected(IoError::NotFound, "/missing")
+    );
+    assert_eq!(
+        error(fs.rename("/dir/file.txt", "/dir/sub")),
+        expected(IoError::DirectoryNotEmpty, "/dir/sub")
+    );
+    assert_eq!(
+        error(fs.rename("/dir/file.txt", "/dir/file.txt/a")),
+        expected(IoError::NotADirectory, "/dir/file.txt/a")
+    );
+
+    // Nothing changed.
+    assert_eq!(fs.read("/dir/file.txt")?, b"content");
+    assert_eq!(fs.read("/dir/sub/a.txt")?, b"a");
+    assert!(!fs.exists("/other"));
+    Ok(())
 }
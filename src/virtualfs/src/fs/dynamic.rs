@@ -0,0 +1,157 @@
+use crate::error::Result;
+use crate::fs::{DirEntry, FileSystem, Metadata};
+use crate::path::Path;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// An object-safe counterpart of [`FileSystem`], so the backend can be chosen at runtime
+/// with `Box<dyn DynFileSystem>`.
+///
+/// Every FileSystem is a DynFileSystem, and boxes and mutable references to a
+/// DynFileSystem are FileSystems again, so they can be used with the generic wrappers
+/// (e.g. `OverlayFileSystem<Box<dyn DynFileSystem>>`). Its methods are prefixed with
+/// `dyn_` so they do not shadow the FileSystem ones when both traits are in scope.
+pub trait DynFileSystem: Send {
+    fn dyn_read(&self, path: &Path) -> Result<&[u8]>;
+    fn dyn_exists(&self, path: &Path) -> bool;
+    fn dyn_read_dir(&self, path: &Path) -> Result<Vec<DirEntry>>;
+    fn dyn_metadata(&self, path: &Path) -> Result<Metadata>;
+
+    fn dyn_write(&mut self, path: &Path, content: &[u8]) -> Result<()>;
+    fn dyn_delete(&mut self, path: &Path) -> Result<()>;
+    fn dyn_rename(&mut self, from: &Path, to: &Path) -> Result<()>;
+}
+
+impl<Fs: FileSystem> DynFileSystem for Fs {
+    fn dyn_read(&self, path: &Path) -> Result<&[u8]> {
+        FileSystem::read(self, path)
+    }
+
+    fn dyn_exists(&self, path: &Path) -> bool {
+        FileSystem::exists(self, path)
+    }
+
+    fn dyn_read_dir(&self, path: &Path) -> Result<Vec<DirEntry>> {
+        FileSystem::read_dir(self, path)
+    }
+
+    fn dyn_metadata(&self, path: &Path) -> Result<Metadata> {
+        FileSystem::metadata(self, path)
+    }
+
+    fn dyn_write(&mut self, path: &Path, content: &[u8]) -> Result<()> {
+        FileSystem::write(self, path, content)
+    }
+
+    fn dyn_delete(&mut self, path: &Path) -> Result<()> {
+        FileSystem::delete(self, path)
+    }
+
+    fn dyn_rename(&mut self, from: &Path, to: &Path) -> Result<()> {
+        FileSystem::rename(self, from, to)
+    }
+}
+
+macro_rules! impl_file_system {
+    ($ty:ty) => {
+        impl<T: DynFileSystem + ?Sized> FileSystem for $ty {
+            fn read<P: AsRef<Path>>(&self, path: P) -> Result<&[u8]> {
+                DynFileSystem::dyn_read(&**self, path.as_ref())
+            }
+
+            fn exists<P: AsRef<Path>>(&self, path: P) -> bool {
+                DynFileSystem::dyn_exists(&**self, path.as_ref())
+            }
+
+            fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Vec<DirEntry>> {
+                DynFileSystem::dyn_read_dir(&**self, path.as_ref())
+            }
+
+            fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Metadata> {
+                DynFileSystem::dyn_metadata(&**self, path.as_ref())
+            }
+
+            fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&mut self, path: P, content: C) -> Result<()> {
+                DynFileSystem::dyn_write(&mut **self, path.as_ref(), content.as_ref())
+            }
+
+            fn delete<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+                DynFileSystem::dyn_delete(&mut **self, path.as_ref())
+            }
+
+            fn rename<From: AsRef<Path>, To: AsRef<Path>>(
+                &mut self,
+                from: From,
+                to: To,
+            ) -> Result<()> {
+                DynFileSystem::dyn_rename(&mut **self, from.as_ref(), to.as_ref())
+            }
+        }
+    };
+}
+
+impl_file_system!(Box<T>);
+impl_file_system!(&mut T);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::{MemoryFileSystem, OverlayFileSystem, RecordingFileSystem};
+    use alloc::vec;
+
+    fn backend(recording: bool) -> Box<dyn DynFileSystem> {
+        if recording {
+            Box::new(RecordingFileSystem::new(MemoryFileSystem::default()))
+        } else {
+            Box::new(MemoryFileSystem::default())
+        }
+    }
+
+    #[test]
+    fn boxed() -> Result<()> {
+        for recording in [false, true] {
+            let mut fs = backend(recording);
+            fs.write("/a/hello.txt", "blue")?;
+            assert_eq!(fs.read("/a/hello.txt")?, b"blue");
+            assert!(fs.metadata("/a")?.is_dir());
+            assert_eq!(fs.read_dir("/a")?[0].name, "hello.txt");
+
+            fs.rename("/a/hello.txt", "/b/hello.txt")?;
+            assert_eq!(
+                fs.walk("/").filter_map(|entry| entry.ok()).count(),
+                3,
+                "/a, /b and /b/hello.txt"
+            );
+            fs.delete("/b")?;
+            assert!(!fs.exists("/b/hello.txt"));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn error_contract() -> Result<()> {
+        crate::fs::check_error_contract(&mut backend(false))?;
+        crate::fs::check_error_contract(&mut backend(true))
+    }
+
+    #[test]
+    fn compose() -> Result<()> {
+        let mut base = MemoryFileSystem::default();
+        base.write("/README.md", "readme")?;
+
+        let mut overlay = OverlayFileSystem::new(Box::new(base) as Box<dyn DynFileSystem>);
+        overlay.write("/README.md", "changed")?;
+        assert_eq!(overlay.base().read("/README.md")?, b"readme");
+
+        let mut backends: Vec<Box<dyn DynFileSystem>> =
+            vec![Box::new(overlay), Box::new(MemoryFileSystem::default())];
+        for fs in &mut backends {
+            let mut recording = RecordingFileSystem::new(&mut **fs);
+            recording.write("/new.txt", "new")?;
+            assert_eq!(recording.actions().len(), 1);
+        }
+        assert_eq!(backends[0].read("/README.md")?, b"changed");
+        assert_eq!(backends[1].read("/new.txt")?, b"new");
+        Ok(())
+    }
+}
@@ -0,0 +1,348 @@
+use crate::error::{Error, IoError, Result};
+use crate::fs::FileSystem;
+use crate::path::{OwnedPath, Path};
+use std::borrow::ToOwned;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::vec::Vec;
+
+/// Move an offset as described by a `SeekFrom`, refusing to go before the start.
+fn seek_offset(offset: usize, len: usize, pos: SeekFrom) -> std::io::Result<usize> {
+    let (base, delta) = match pos {
+        SeekFrom::Start(start) => return Ok(start as usize),
+        SeekFrom::End(delta) => (len, delta),
+        SeekFrom::Current(delta) => (offset, delta),
+    };
+
+    base.checked_add_signed(delta as isize).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "invalid seek to a negative or overflowing position",
+        )
+    })
+}
+
+/// Copy the content starting at an offset into a buffer, returning the length copied.
+fn read_at(content: &[u8], offset: usize, buf: &mut [u8]) -> usize {
+    let rest = content.get(offset..).unwrap_or_default();
+    let len = std::cmp::min(rest.len(), buf.len());
+    buf[..len].copy_from_slice(&rest[..len]);
+    len
+}
+
+/// A read-only handle to a file of a FileSystem. The content is read from the file system
+/// on every call, starting at the offset of the handle.
+pub struct Entry<'a, Fs: FileSystem + Sized> {
+    fs: &'a Fs,
+    offset: usize,
+    path: OwnedPath,
+}
+
+impl<'a, Fs: FileSystem> Entry<'a, Fs> {
+    pub fn new<P: AsRef<Path>>(fs: &'a Fs, path: P) -> Self {
+        Entry {
+            fs,
+            offset: 0,
+            path: path.as_ref().to_owned(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl<'a, Fs: FileSystem> Read for Entry<'a, Fs> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes: &[u8] = self
+            .fs
+            .read(&self.path)
+            .map_err(std::convert::Into::<std::io::Error>::into)?;
+        let len = read_at(bytes, self.offset, buf);
+        self.offset += len;
+        Ok(len)
+    }
+}
+
+impl<'a, Fs: FileSystem> Seek for Entry<'a, Fs> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let len = match pos {
+            SeekFrom::End(_) => self
+                .fs
+                .read(&self.path)
+                .map_err(std::convert::Into::<std::io::Error>::into)?
+                .len(),
+            _ => 0,
+        };
+        self.offset = seek_offset(self.offset, len, pos)?;
+        Ok(self.offset as u64)
+    }
+}
+
+/// Options to open a writable handle to a file, akin to [`std::fs::OpenOptions`].
+#[derive(Clone, Debug, Default)]
+pub struct OpenOptions {
+    create: bool,
+    create_new: bool,
+    truncate: bool,
+    append: bool,
+}
+
+impl OpenOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create the file if it does not exist.
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.create = create;
+        self
+    }
+
+    /// Create the file, failing if it already exists. This implies `create`.
+    pub fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.create_new = create_new;
+        self
+    }
+
+    /// Start with empty content instead of the content of the file.
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Write at the end of the content, whatever the offset of the handle.
+    pub fn append(&mut self, append: bool) -> &mut Self {
+        self.append = append;
+        self
+    }
+
+    /// Open a file of a file system with these options.
+    pub fn open<'a, Fs: FileSystem, P: AsRef<Path>>(
+        &self,
+        fs: &'a mut Fs,
+        path: P,
+    ) -> Result<EntryMut<'a, Fs>> {
+        let path = path.as_ref();
+        let exists = fs.exists(path);
+        if self.create_new && exists {
+            return Err(Error::path(path, IoError::AlreadyExists));
+        }
+
+        let content = if exists {
+            if self.truncate {
+                Vec::new()
+            } else {
+                fs.read(path)?.to_vec()
+            }
+        } else if self.create || self.create_new {
+            fs.write(path, [])?;
+            Vec::new()
+        } else {
+            return Err(Error::path(path, IoError::NotFound));
+        };
+
+        Ok(EntryMut {
+            fs,
+            offset: 0,
+            path: path.to_owned(),
+            content,
+            append: self.append,
+            dirty: exists && self.truncate,
+        })
+    }
+}
+
+/// A writable handle to a file of a FileSystem, created with [`OpenOptions`].
+///
+/// Writes are buffered in the handle, and committed to the file system when the handle is
+/// flushed or dropped. Errors committing on drop are ignored; call `flush` to handle them.
+pub struct EntryMut<'a, Fs: FileSystem + Sized> {
+    fs: &'a mut Fs,
+    offset: usize,
+    path: OwnedPath,
+    content: Vec<u8>,
+    append: bool,
+    dirty: bool,
+}
+
+impl<'a, Fs: FileSystem> EntryMut<'a, Fs> {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The content of the file, including the writes not yet committed.
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// Resize the content, filling it with zeros if it grows.
+    pub fn set_len(&mut self, len: usize) {
+        self.content.resize(len, 0);
+        self.dirty = true;
+    }
+}
+
+impl<'a, Fs: FileSystem> Read for EntryMut<'a, Fs> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = read_at(&self.content, self.offset, buf);
+        self.offset += len;
+        Ok(len)
+    }
+}
+
+impl<'a, Fs: FileSystem> Seek for EntryMut<'a, Fs> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.offset = seek_offset(self.offset, self.content.len(), pos)?;
+        Ok(self.offset as u64)
+    }
+}
+
+impl<'a, Fs: FileSystem> Write for EntryMut<'a, Fs> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.append {
+            self.offset = self.content.len();
+        }
+
+        let end = self.offset + buf.len();
+        if self.content.len() < end {
+            self.content.resize(end, 0);
+        }
+        self.content[self.offset..end].copy_from_slice(buf);
+        self.offset = end;
+        self.dirty = true;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.dirty {
+            self.fs
+                .write(&self.path, &self.content)
+                .map_err(std::convert::Into::<std::io::Error>::into)?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, Fs: FileSystem> Drop for EntryMut<'a, Fs> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::MemoryFileSystem;
+    use std::string::String;
+
+    #[test]
+    fn read() -> std::io::Result<()> {
+        let content = (0..10_000).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+        let mut fs = MemoryFileSystem::default();
+        fs.write("/a.bin", &content)?;
+
+        let mut entry = fs.entry("/a.bin")?;
+        let mut buf = [0; 3];
+        entry.read_exact(&mut buf)?;
+        assert_eq!(buf, [0, 1, 2]);
+        entry.read_exact(&mut buf)?;
+        assert_eq!(buf, [3, 4, 5]);
+
+        let mut rest = Vec::new();
+        entry.read_to_end(&mut rest)?;
+        assert_eq!(rest, content[6..]);
+        assert_eq!(entry.read(&mut buf)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn seek() -> std::io::Result<()> {
+        let mut fs = MemoryFileSystem::default();
+        fs.write("/a.txt", "hello world")?;
+
+        let mut entry = fs.entry("/a.txt")?;
+        assert_eq!(entry.seek(SeekFrom::End(-5))?, 6);
+        let mut rest = String::new();
+        entry.read_to_string(&mut rest)?;
+        assert_eq!(rest, "world");
+
+        assert_eq!(entry.seek(SeekFrom::Start(2))?, 2);
+        assert_eq!(entry.seek(SeekFrom::Current(2))?, 4);
+        let mut buf = [0; 3];
+        entry.read_exact(&mut buf)?;
+        assert_eq!(&buf, b"o w");
+
+        assert!(entry.seek(SeekFrom::Current(-10)).is_err());
+        assert_eq!(entry.seek(SeekFrom::Start(20))?, 20);
+        assert_eq!(entry.read(&mut buf)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn write() -> std::io::Result<()> {
+        let mut fs = MemoryFileSystem::default();
+        fs.write("/a.txt", "hello world")?;
+
+        let mut entry = OpenOptions::new().open(&mut fs, "/a.txt")?;
+        entry.seek(SeekFrom::Start(6))?;
+        entry.write_all(b"there!")?;
+        assert_eq!(entry.content(), b"hello there!");
+        entry.flush()?;
+        drop(entry);
+        assert_eq!(fs.read("/a.txt")?, b"hello there!");
+
+        // Writes are committed on drop.
+        let mut entry = OpenOptions::new().open(&mut fs, "/a.txt")?;
+        entry.seek(SeekFrom::End(2))?;
+        entry.write_all(b"?")?;
+        drop(entry);
+        assert_eq!(fs.read("/a.txt")?, b"hello there!\0\0?");
+        Ok(())
+    }
+
+    #[test]
+    fn options() -> std::io::Result<()> {
+        let mut fs = MemoryFileSystem::default();
+        assert_eq!(
+            OpenOptions::new()
+                .open(&mut fs, "/a.txt")
+                .err()
+                .and_then(|err| err.get_io().copied()),
+            Some(IoError::NotFound)
+        );
+
+        OpenOptions::new().create(true).open(&mut fs, "/a.txt")?;
+        assert_eq!(fs.read("/a.txt")?, b"");
+
+        let mut entry = OpenOptions::new().append(true).open(&mut fs, "/a.txt")?;
+        entry.write_all(b"hello")?;
+        entry.seek(SeekFrom::Start(0))?;
+        entry.write_all(b" world")?;
+        drop(entry);
+        assert_eq!(fs.read("/a.txt")?, b"hello world");
+
+        let mut entry = OpenOptions::new().truncate(true).open(&mut fs, "/a.txt")?;
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        assert!(content.is_empty());
+        drop(entry);
+        assert_eq!(fs.read("/a.txt")?, b"");
+
+        assert_eq!(
+            OpenOptions::new()
+                .create_new(true)
+                .open(&mut fs, "/a.txt")
+                .err()
+                .and_then(|err| err.get_io().copied()),
+            Some(IoError::AlreadyExists)
+        );
+        let mut entry = OpenOptions::new()
+            .create_new(true)
+            .open(&mut fs, "/b.txt")?;
+        entry.write_all(b"b")?;
+        drop(entry);
+        assert_eq!(fs.read("/b.txt")?, b"b");
+        Ok(())
+    }
+}
@@ -1,34 +1,15 @@
 use crate::error::{Error, IoError, Result};
-use crate::fs::FileSystem;
+use crate::fs::{content_hash, Clock, DirEntry, EntryKind, FileSystem, Metadata};
 use crate::path::{Component, OwnedPath, Path};
-use std::collections::BTreeMap;
-use std::fmt::Formatter;
-use thiserror::Error as DeriveError;
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::time::Duration;
 
-type MemFsResult<X> = std::result::Result<X, MemFsError>;
-
-pub struct InternalError(Box<dyn FnOnce(&Path) -> MemFsError + Send + Sync>);
-
-impl std::fmt::Debug for InternalError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str("Internal Error.")
-    }
-}
-
-#[derive(Debug, DeriveError)]
-pub enum MemFsError {
-    #[error("Path {0} could not be found.")]
-    PathNotFound(OwnedPath),
-
-    #[error("Path {0} is not a directory (but was expected to be).")]
-    PathIsNotADirectory(OwnedPath),
-
-    #[error("Path {0} points to a directory (was expecting a file).")]
-    PathIsNotAFile(OwnedPath),
-
-    #[error("Internal Error (unexpected)")]
-    _Internal(InternalError),
-}
+type MemFsResult<X> = core::result::Result<X, IoError>;
 
 #[derive(Clone, Debug)]
 enum MemFsEntryKind {
@@ -40,48 +21,76 @@ enum MemFsEntryKind {
     },
 }
 
-fn not_found() -> MemFsError {
-    MemFsError::_Internal(InternalError(Box::new(|path| {
-        MemFsError::PathNotFound(path.to_owned())
-    })))
+fn not_found() -> IoError {
+    IoError::NotFound
 }
 
-fn expected_a_file() -> MemFsError {
-    MemFsError::_Internal(InternalError(Box::new(|path| {
-        MemFsError::PathIsNotAFile(path.to_owned())
-    })))
+fn expected_a_file() -> IoError {
+    IoError::IsADirectory
 }
 
-fn expected_a_dir() -> MemFsError {
-    MemFsError::_Internal(InternalError(Box::new(|path| {
-        MemFsError::PathIsNotADirectory(path.to_owned())
-    })))
+fn expected_a_dir() -> IoError {
+    IoError::NotADirectory
 }
 
-fn map_memfs_error(path: &Path, err: MemFsError) -> Error {
-    match err {
-        MemFsError::_Internal(InternalError(f)) => Error::custom(f(path)),
-        x => Error::custom(x),
+fn map_memfs_error(path: &Path, err: IoError) -> Error {
+    Error::path(path, err)
+}
+
+/// Report an error of an operation on a parent as an error on the path itself.
+fn map_parent_error(path: &Path) -> impl FnOnce(Error) -> Error + '_ {
+    move |err| match err {
+        Error::Path { kind, .. } => Error::path(path, kind),
+        err => err,
     }
 }
 
 #[derive(Clone, Debug)]
 struct MemFsEntry {
     pub kind: MemFsEntryKind,
+    pub created: Duration,
+    pub modified: Duration,
+    pub readonly: bool,
 }
 
 impl MemFsEntry {
-    pub fn dir() -> Self {
+    pub fn dir(now: Duration) -> Self {
         Self {
             kind: MemFsEntryKind::Directory {
                 entries: Default::default(),
             },
+            created: now,
+            modified: now,
+            readonly: false,
         }
     }
 
-    pub fn file(content: Vec<u8>) -> Self {
+    pub fn file(content: Vec<u8>, now: Duration) -> Self {
         Self {
             kind: MemFsEntryKind::File { content },
+            created: now,
+            modified: now,
+            readonly: false,
+        }
+    }
+
+    pub fn metadata(&self) -> Metadata {
+        let (kind, len, hash) = match &self.kind {
+            MemFsEntryKind::Directory { .. } => (EntryKind::Directory, 0, None),
+            MemFsEntryKind::File { content } => (
+                EntryKind::File,
+                content.len() as u64,
+                Some(content_hash(content)),
+            ),
+        };
+
+        Metadata {
+            kind,
+            len,
+            created: Some(self.created),
+            modified: Some(self.modified),
+            readonly: self.readonly,
+            hash,
         }
     }
 
@@ -97,19 +106,14 @@ impl MemFsEntry {
     pub fn set_or_replace_entry(&mut self, name: String, entry: MemFsEntry) -> MemFsResult<()> {
         match &mut self.kind {
             MemFsEntryKind::Directory { entries } => {
-                let kind = entry.kind;
-
-                entries
-                    .entry(name)
-                    .and_modify(|e| e.kind = kind.clone())
-                    .or_insert_with(|| MemFsEntry { kind });
-
+                entries.insert(name, entry);
                 Ok(())
             }
             _ => Err(expected_a_dir()),
         }
     }
 
+    #[allow(dead_code)]
     pub fn as_dir(&self) -> MemFsResult<&Self> {
         match &self.kind {
             MemFsEntryKind::Directory { .. } => Ok(self),
@@ -124,6 +128,7 @@ impl MemFsEntry {
         }
     }
 
+    #[allow(dead_code)]
     pub fn as_dir_mut(&mut self) -> MemFsResult<&mut Self> {
         match &self.kind {
             MemFsEntryKind::Directory { .. } => Ok(self),
@@ -131,6 +136,7 @@ impl MemFsEntry {
         }
     }
 
+    #[allow(dead_code)]
     pub fn as_file_mut(&mut self) -> MemFsResult<&mut Self> {
         match &self.kind {
             MemFsEntryKind::File { .. } => Ok(self),
@@ -153,10 +159,11 @@ impl MemFsEntry {
         }
     }
 
-    pub fn write(&mut self, new_content: &[u8]) -> MemFsResult<()> {
+    pub fn write(&mut self, new_content: &[u8], now: Duration) -> MemFsResult<()> {
         match &mut self.kind {
             MemFsEntryKind::File { content } => {
                 *content = new_content.to_vec();
+                self.modified = now;
                 Ok(())
             }
             _ => Err(expected_a_file()),
@@ -181,11 +188,15 @@ impl MemFsEntry {
         }
     }
 
-    pub fn get_or_create_dir(&mut self, name: &Component) -> MemFsResult<&mut MemFsEntry> {
+    pub fn get_or_create_dir(
+        &mut self,
+        name: &Component,
+        now: Duration,
+    ) -> MemFsResult<&mut MemFsEntry> {
         match &mut self.kind {
             MemFsEntryKind::Directory { entries } => Ok(entries
                 .entry(name.to_string())
-                .or_insert_with(|| MemFsEntry::dir()))
+                .or_insert_with(|| MemFsEntry::dir(now)))
             .and_then(|entry| {
                 if !entry.is_dir() {
                     Err(expected_a_dir())
@@ -198,14 +209,18 @@ impl MemFsEntry {
         }
     }
 
-    pub fn get_or_create_file(&mut self, name: &Component) -> MemFsResult<&mut MemFsEntry> {
+    pub fn get_or_create_file(
+        &mut self,
+        name: &Component,
+        now: Duration,
+    ) -> MemFsResult<&mut MemFsEntry> {
         match &mut self.kind {
             MemFsEntryKind::Directory { entries } => Ok(entries
                 .entry(name.to_string())
-                .or_insert_with(|| MemFsEntry::file(Default::default())))
+                .or_insert_with(|| MemFsEntry::file(Default::default(), now)))
             .and_then(|entry| {
                 if !entry.is_file() {
-                    Err(expected_a_dir())
+                    Err(expected_a_file())
                 } else {
                     Ok(entry)
                 }
@@ -215,19 +230,20 @@ impl MemFsEntry {
     }
 }
 
+/// A FileSystem held in memory. Entries are timestamped using a [`Clock`], which is the
+/// host's clock by default (or the Unix epoch without the `std` feature).
 pub struct MemoryFileSystem {
     root: MemFsEntry,
+    clock: Box<dyn Clock>,
 }
 
 impl Default for MemoryFileSystem {
     fn default() -> Self {
-        Self {
-            root: MemFsEntry {
-                kind: MemFsEntryKind::Directory {
-                    entries: Default::default(),
-                },
-            },
-        }
+        #[cfg(feature = "std")]
+        let clock = crate::fs::SystemClock;
+        #[cfg(not(feature = "std"))]
+        let clock = crate::fs::EpochClock;
+        Self::with_clock(clock)
     }
 }
 
@@ -236,27 +252,54 @@ impl MemoryFileSystem {
         Default::default()
     }
 
+    /// Create an empty file system timestamping its entries with a clock.
+    pub fn with_clock<C: Clock + 'static>(clock: C) -> Self {
+        Self {
+            root: MemFsEntry::dir(clock.now()),
+            clock: Box::new(clock),
+        }
+    }
+
+    /// Mark an entry as read-only (or not). Read-only entries cannot be written, deleted
+    /// or renamed.
+    pub fn set_readonly<P: AsRef<Path>>(&mut self, path: P, readonly: bool) -> Result<()> {
+        self.get_entry_mut(path.as_ref())?.readonly = readonly;
+        Ok(())
+    }
+
+    fn check_writable(&self, path: &Path) -> Result<()> {
+        match self.get_entry(path) {
+            Ok(entry) if entry.readonly => Err(Error::path(path, IoError::PermissionDenied)),
+            _ => Ok(()),
+        }
+    }
+
     fn get_dir_or_create(&mut self, path: &Path) -> Result<&mut MemFsEntry> {
+        let now = self.clock.now();
         path.iter()
-            .fold(Ok(&mut self.root), |acc, entry_name| {
-                acc?.get_or_create_dir(&entry_name)
+            .try_fold(&mut self.root, |acc, entry_name| {
+                acc.get_or_create_dir(&entry_name, now)
             })
             .map_err(|err| map_memfs_error(path, err))
     }
 
     fn get_file_or_create(&mut self, path: &Path) -> Result<&mut MemFsEntry> {
-        let dir = self.get_dir_or_create(&path.parent().unwrap())?;
-        dir.get_or_create_file(&path.basename().unwrap())
+        let now = self.clock.now();
+        let dir = self
+            .get_dir_or_create(path.parent().unwrap())
+            .map_err(map_parent_error(path))?;
+        dir.get_or_create_file(&path.basename().unwrap(), now)
             .map_err(|err| map_memfs_error(path, err))
     }
 
     fn get_entry(&self, path: &Path) -> Result<&MemFsEntry> {
-        path.iter().fold(Ok(&self.root), |acc, entry_name| {
-            acc?.get_entry(&entry_name)
+        path.iter().try_fold(&self.root, |acc, entry_name| {
+            acc.get_entry(&entry_name)
                 .map_err(|err| map_memfs_error(path, err))
         })
     }
 
+    #[allow(dead_code)]
     fn get_dir(&self, path: &Path) -> Result<&MemFsEntry> {
         self.get_entry(path)?
             .as_dir()
@@ -270,18 +313,49 @@ impl MemoryFileSystem {
     }
 
     fn get_entry_mut(&mut self, path: &Path) -> Result<&mut MemFsEntry> {
-        path.iter().fold(Ok(&mut self.root), |acc, entry_name| {
-            acc?.get_entry_mut(&entry_name)
+        path.iter().try_fold(&mut self.root, |acc, entry_name| {
+            acc.get_entry_mut(&entry_name)
                 .map_err(|err| map_memfs_error(path, err))
         })
     }
 
+    #[allow(dead_code)]
     fn get_dir_mut(&mut self, path: &Path) -> Result<&mut MemFsEntry> {
         self.get_entry_mut(path)?
             .as_dir_mut()
             .map_err(|err| map_memfs_error(path, err))
     }
 
+    /// Every file of the file system, with its content, sorted by path.
+    pub(crate) fn files(&self) -> Vec<(OwnedPath, &[u8])> {
+        fn collect<'a>(
+            entry: &'a MemFsEntry,
+            path: Option<&str>,
+            files: &mut Vec<(OwnedPath, &'a [u8])>,
+        ) {
+            match &entry.kind {
+                MemFsEntryKind::Directory { entries } => {
+                    for (name, child) in entries {
+                        let child_path = match path {
+                            Some(path) => format!("{}/{}", path, name),
+                            None => name.clone(),
+                        };
+                        collect(child, Some(&child_path), files);
+                    }
+                }
+                MemFsEntryKind::File { content } => files.push((
+                    OwnedPath::from(path.unwrap_or_default().to_owned()),
+                    content,
+                )),
+            }
+        }
+
+        let mut files = Vec::new();
+        collect(&self.root, None, &mut files);
+        files
+    }
+
+    #[allow(dead_code)]
     fn get_file_mut(&mut self, path: &Path) -> Result<&mut MemFsEntry> {
         self.get_entry_mut(path)?
             .as_file_mut()
@@ -300,57 +374,100 @@ impl FileSystem for MemoryFileSystem {
         self.get_entry(path.as_ref()).is_ok()
     }
 
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Vec<DirEntry>> {
+        let path = path.as_ref();
+        let dir = match self.get_entry(path) {
+            Ok(dir) => dir,
+            // The root directory is only created with its first entry.
+            Err(_) if path.is_root() => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        match &dir.kind {
+            MemFsEntryKind::Directory { entries } => Ok(entries
+                .iter()
+                .map(|(name, entry)| {
+                    let kind = if entry.is_dir() {
+                        EntryKind::Directory
+                    } else {
+                        EntryKind::File
+                    };
+                    DirEntry::new(path, name, kind)
+                })
+                .collect()),
+            _ => Err(map_memfs_error(path, expected_a_dir())),
+        }
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Metadata> {
+        let path = path.as_ref();
+        match self.get_entry(path) {
+            Ok(entry) => Ok(entry.metadata()),
+            Err(_) if path.is_root() => Ok(self.root.metadata()),
+            Err(err) => Err(err),
+        }
+    }
+
     fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&mut self, path: P, content: C) -> Result<()> {
+        self.check_writable(path.as_ref())?;
+        let now = self.clock.now();
         self.get_file_or_create(path.as_ref())?
-            .write(content.as_ref())
+            .write(content.as_ref(), now)
             .map_err(|err| map_memfs_error(path.as_ref(), err))
     }
 
     fn delete<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        self.get_entry_mut(
-            path.as_ref()
-                .parent()
-                .ok_or_else(|| Error::Io(IoError::NotFound))?,
-        )?
-        .delete(
-            &path
-                .as_ref()
-                .basename()
-                .ok_or_else(|| Error::Io(IoError::NotFound))?,
-        )
-        .map(|_| ())
-        .map_err(|err| map_memfs_error(path.as_ref(), err))
+        let path = path.as_ref();
+        self.check_writable(path)?;
+        let not_found = || Error::path(path, IoError::NotFound);
+        self.get_entry_mut(path.parent().ok_or_else(not_found)?)
+            .map_err(map_parent_error(path))?
+            .delete(path.basename().ok_or_else(not_found)?)
+            .map(|_| ())
+            .map_err(|err| map_memfs_error(path, err))
     }
 
+    /// Rename an entry, replacing a file or an empty directory at the destination.
     fn rename<From: AsRef<Path>, To: AsRef<Path>>(&mut self, from: From, to: To) -> Result<()> {
-        let from_basename = from
-            .as_ref()
-            .basename()
-            .ok_or_else(|| Error::Io(IoError::NotFound))?;
-        let from_parent = from
-            .as_ref()
-            .parent()
-            .ok_or_else(|| Error::Io(IoError::NotFound))?;
-        let to_basename = to
-            .as_ref()
-            .basename()
-            .ok_or_else(|| Error::Io(IoError::NotFound))?;
-        let to_parent = to
-            .as_ref()
-            .parent()
-            .ok_or_else(|| Error::Io(IoError::NotFound))?;
-        let from_dir = self.get_entry_mut(from_parent)?;
-
-        let content = from_dir
-            .delete(from_basename)
-            .map_err(|err| map_memfs_error(from.as_ref(), err))?;
+        let (from, to) = (from.as_ref(), to.as_ref());
+        self.check_writable(from)?;
+        self.check_writable(to)?;
+        let from_not_found = || Error::path(from, IoError::NotFound);
+        let to_not_found = || Error::path(to, IoError::NotFound);
+        let from_basename = from.basename().ok_or_else(from_not_found)?;
+        let from_parent = from.parent().ok_or_else(from_not_found)?;
+        let to_basename = to.basename().ok_or_else(to_not_found)?;
+        let to_parent = to.parent().ok_or_else(to_not_found)?;
+
+        let from_is_dir = self.get_entry(from)?.is_dir();
+        if let Ok(MemFsEntry {
+            kind: MemFsEntryKind::Directory { entries },
+            ..
+        }) = self.get_entry(to)
+        {
+            if !entries.is_empty() {
+                return Err(Error::path(to, IoError::DirectoryNotEmpty));
+            }
+        }
 
-        let to_dir = self.get_dir_or_create(to_parent)?;
-        to_dir
-            .set_or_replace_entry(to_basename.to_string(), content)
-            .map_err(|err| map_memfs_error(to.as_ref(), err))?;
+        // A directory cannot be moved inside itself.
+        if from_is_dir
+            && to.iter().count() > from.iter().count()
+            && to.iter().zip(from.iter()).all(|(a, b)| a == b)
+        {
+            return Err(Error::path(to, IoError::InvalidInput));
+        }
+        // Create the destination's parents first, so errors do not lose the entry.
+        self.get_dir_or_create(to_parent)
+            .map_err(map_parent_error(to))?;
 
-        Ok(())
+        let content = self
+            .get_entry_mut(from_parent)?
+            .delete(from_basename)
+            .map_err(|err| map_memfs_error(from, err))?;
+        self.get_dir_or_create(to_parent)?
+            .set_or_replace_entry(to_basename.to_string(), content)
+            .map_err(|err| map_memfs_error(to, err))
     }
 }
 
@@ -358,6 +475,8 @@ impl FileSystem for MemoryFileSystem {
 mod tests {
     use super::*;
     use crate::error::Result;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
 
     #[test]
     fn base() -> Result<()> {
@@ -390,6 +509,94 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn read_dir() -> Result<()> {
+        let mut fs = MemoryFileSystem::default();
+        assert_eq!(fs.read_dir("/")?, []);
+
+        fs.write("/b.txt", "b")?;
+        fs.write("/a/c.txt", "c")?;
+        let entries = fs.read_dir("/")?;
+        assert_eq!(
+            entries
+                .iter()
+                .map(|e| (AsRef::<str>::as_ref(e.path.as_path()), e.kind))
+                .collect::<Vec<(&str, EntryKind)>>(),
+            [("/a", EntryKind::Directory), ("/b.txt", EntryKind::File)]
+        );
+        assert_eq!(fs.read_dir("/a")?[0].name, "c.txt");
+        assert!(fs.read_dir("/b.txt").is_err());
+        assert!(fs.read_dir("/d").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn metadata() -> Result<()> {
+        let time = Arc::new(AtomicU64::new(10));
+        let clock = {
+            let time = time.clone();
+            move || Duration::from_secs(time.load(Ordering::SeqCst))
+        };
+        let mut fs = MemoryFileSystem::with_clock(clock);
+        fs.write("/a/hello.txt", "blue")?;
+
+        let metadata = fs.metadata("/a/hello.txt")?;
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len, 4);
+        assert_eq!(metadata.created, Some(Duration::from_secs(10)));
+        assert_eq!(metadata.modified, Some(Duration::from_secs(10)));
+        assert!(!metadata.readonly);
+        assert_eq!(metadata.hash, Some(content_hash(b"blue")));
+
+        time.store(20, Ordering::SeqCst);
+        fs.write("/a/hello.txt", "green")?;
+        let metadata = fs.metadata("/a/hello.txt")?;
+        assert_eq!(metadata.len, 5);
+        assert_eq!(metadata.created, Some(Duration::from_secs(10)));
+        assert_eq!(metadata.modified, Some(Duration::from_secs(20)));
+
+        fs.rename("/a/hello.txt", "/b/hello.txt")?;
+        assert_eq!(fs.metadata("/b/hello.txt")?, metadata);
+
+        let metadata = fs.metadata("/a")?;
+        assert!(metadata.is_dir());
+        assert_eq!(metadata.len, 0);
+        assert_eq!(metadata.hash, None);
+        assert!(fs.metadata("/").is_ok());
+        assert!(fs.metadata("/c").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn readonly() -> Result<()> {
+        let mut fs = MemoryFileSystem::default();
+        fs.write("/hello.txt", "blue")?;
+        fs.set_readonly("/hello.txt", true)?;
+        assert!(fs.metadata("/hello.txt")?.readonly);
+
+        for result in [
+            fs.write("/hello.txt", "green"),
+            fs.delete("/hello.txt"),
+            fs.rename("/hello.txt", "/world.txt"),
+        ] {
+            assert_eq!(
+                result.unwrap_err().get_io(),
+                Some(&IoError::PermissionDenied)
+            );
+        }
+        assert_eq!(fs.read("/hello.txt")?, b"blue");
+
+        fs.set_readonly("/hello.txt", false)?;
+        fs.write("/hello.txt", "green")?;
+        assert!(fs.set_readonly("/world.txt", true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn error_contract() -> Result<()> {
+        crate::fs::check_error_contract(&mut MemoryFileSystem::default())
+    }
+
     #[test]
     fn base_err() -> Result<()> {
         let fs = MemoryFileSystem::default();
@@ -0,0 +1,84 @@
+use crate::fs::EntryKind;
+use core::time::Duration;
+
+/// A source of time for the timestamps of a FileSystem, as a duration since the Unix epoch.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Duration;
+}
+
+/// The clock of the host.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+/// A clock stuck at the Unix epoch, used by default when there is no host clock (without
+/// the `std` feature).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EpochClock;
+
+impl Clock for EpochClock {
+    fn now(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+impl<F: Fn() -> Duration + Send + Sync> Clock for F {
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+/// The metadata of an entry of a FileSystem, as returned by `FileSystem::metadata`.
+///
+/// Timestamps are durations since the Unix epoch, and are `None` when the backend does not
+/// track them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Metadata {
+    pub kind: EntryKind,
+    /// The length of a file's content in bytes, 0 for directories.
+    pub len: u64,
+    pub created: Option<Duration>,
+    pub modified: Option<Duration>,
+    pub readonly: bool,
+    /// The `content_hash` of a file's content, when the backend can compute it cheaply.
+    pub hash: Option<u64>,
+}
+
+impl Metadata {
+    pub fn is_file(&self) -> bool {
+        self.kind == EntryKind::File
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.kind == EntryKind::Directory
+    }
+}
+
+/// A 64 bits FNV-1a hash of some content. It is stable across platforms and versions, so
+/// it can be stored to detect changes.
+pub fn content_hash(content: &[u8]) -> u64 {
+    content.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash() {
+        assert_eq!(content_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(content_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(content_hash(b"ab"), content_hash(b"ba"));
+    }
+}
@@ -0,0 +1,433 @@
+use crate::error::{Error, IoError, Result};
+use crate::fs::{DirEntry, FileSystem, MemoryFileSystem, Metadata};
+use crate::path::{components, to_path, OwnedPath, Path};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A copy-on-write FileSystem over a base FileSystem. Reads go through to the base, while
+/// changes are staged in memory until they are committed to the base or discarded.
+///
+/// Deleted paths are recorded as whiteouts, hiding everything under them in the base.
+pub struct OverlayFileSystem<Base: FileSystem> {
+    base: Base,
+    layer: MemoryFileSystem,
+    whiteouts: BTreeSet<Vec<String>>,
+}
+
+impl<Base: FileSystem> OverlayFileSystem<Base> {
+    pub fn new(base: Base) -> Self {
+        Self {
+            base,
+            layer: MemoryFileSystem::default(),
+            whiteouts: BTreeSet::new(),
+        }
+    }
+
+    pub fn base(&self) -> &Base {
+        &self.base
+    }
+
+    /// Return the base FileSystem, discarding the staged changes.
+    pub fn into_base(self) -> Base {
+        self.base
+    }
+
+    /// Whether changes are staged.
+    pub fn is_dirty(&self) -> bool {
+        !self.whiteouts.is_empty() || !self.layer.files().is_empty()
+    }
+
+    /// The paths deleted (or renamed) from the base, sorted.
+    pub fn deleted(&self) -> Vec<OwnedPath> {
+        self.whiteouts.iter().map(|c| to_path(c)).collect()
+    }
+
+    /// The paths of the files written, sorted.
+    pub fn written(&self) -> Vec<OwnedPath> {
+        self.layer
+            .files()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    /// Apply the staged changes to the base, deletions first. If applying a change fails,
+    /// the changes applied before it are kept in the base, and every change stays staged.
+    pub fn commit(&mut self) -> Result<()> {
+        for whiteout in &self.whiteouts {
+            let path = to_path(whiteout);
+            if self.base.exists(&path) {
+                self.base.delete(&path)?;
+            }
+        }
+        for (path, content) in self.layer.files() {
+            self.base.write(&path, content)?;
+        }
+
+        self.discard();
+        Ok(())
+    }
+
+    /// Drop the staged changes.
+    pub fn discard(&mut self) {
+        self.layer = MemoryFileSystem::default();
+        self.whiteouts.clear();
+    }
+
+    /// Refuse paths going through a file, whether staged or in the base.
+    fn check_parents(&self, path: &Path) -> Result<()> {
+        let mut parent = components(path);
+        parent.pop();
+        while !parent.is_empty() {
+            if self
+                .metadata(to_path(&parent))
+                .is_ok_and(|metadata| metadata.is_file())
+            {
+                return Err(Error::path(path, IoError::NotADirectory));
+            }
+            parent.pop();
+        }
+        Ok(())
+    }
+
+    fn is_hidden(&self, path: &Path) -> bool {
+        let components = components(path);
+        (1..=components.len()).any(|n| self.whiteouts.contains(&components[..n]))
+    }
+}
+
+impl<Base: FileSystem> FileSystem for OverlayFileSystem<Base> {
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<&[u8]> {
+        let path = to_path(&components(path.as_ref()));
+        if self.layer.exists(&path) {
+            self.layer.read(&path)
+        } else if self.is_hidden(&path) {
+            Err(Error::path(&path, IoError::NotFound))
+        } else {
+            self.base.read(&path).or_else(|err| {
+                // The path may go through a staged file.
+                self.check_parents(&path)?;
+                Err(err)
+            })
+        }
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> bool {
+        let path = to_path(&components(path.as_ref()));
+        self.layer.exists(&path) || (!self.is_hidden(&path) && self.base.exists(&path))
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Vec<DirEntry>> {
+        let path = to_path(&components(path.as_ref()));
+        let base = if self.is_hidden(&path) {
+            Err(Error::path(&path, IoError::NotFound))
+        } else {
+            self.base.read_dir(&path)
+        };
+        let (base, layer) = match self.layer.read_dir(&path) {
+            Ok(layer) => (base.unwrap_or_default(), layer),
+            // A staged file shadows the base.
+            Err(err) if err.get_io() != Some(&IoError::NotFound) => return Err(err),
+            Err(_) => (base?, Vec::new()),
+        };
+
+        let mut entries = BTreeMap::new();
+        for entry in base {
+            if !self.is_hidden(&entry.path) {
+                entries.insert(entry.name.clone(), entry);
+            }
+        }
+        for entry in layer {
+            entries.insert(entry.name.clone(), entry);
+        }
+        Ok(entries.into_values().collect())
+    }
+
+    /// The metadata of a staged file, or else of the base entry. Directories existing in
+    /// the base keep the base metadata.
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Metadata> {
+        let path = to_path(&components(path.as_ref()));
+        let layer = self.layer.metadata(&path);
+        if layer.as_ref().is_ok_and(Metadata::is_file) || self.is_hidden(&path) {
+            return layer;
+        }
+
+        match self.base.metadata(&path) {
+            Ok(metadata) => Ok(metadata),
+            Err(_) if layer.is_ok() => layer,
+            Err(err) => Err(err),
+        }
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&mut self, path: P, content: C) -> Result<()> {
+        let path = to_path(&components(path.as_ref()));
+        self.check_parents(&path)?;
+        if self.metadata(&path).is_ok_and(|metadata| metadata.is_dir()) {
+            return Err(Error::path(&path, IoError::IsADirectory));
+        }
+        self.layer.write(&path, content)
+    }
+
+    fn delete<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let components = components(path.as_ref());
+        let path = to_path(&components);
+        if !self.exists(&path) {
+            return Err(Error::path(&path, IoError::NotFound));
+        }
+        if self.layer.exists(&path) {
+            self.layer.delete(&path)?;
+        }
+        self.whiteouts.insert(components);
+        Ok(())
+    }
+
+    /// Rename a file or a directory, replacing a file or an empty directory at the
+    /// destination. Every file of a renamed directory is copied into the overlay, and the
+    /// original is hidden. As the overlay only stages files, empty directories inside a
+    /// renamed directory are not carried over.
+    fn rename<From: AsRef<Path>, To: AsRef<Path>>(&mut self, from: From, to: To) -> Result<()> {
+        let from = to_path(&components(from.as_ref()));
+        let to = to_path(&components(to.as_ref()));
+        self.check_parents(&to)?;
+
+        let mut files = Vec::new();
+        match self.read(&from) {
+            Ok(content) => files.push((to.clone(), content.to_vec())),
+            Err(_) if self.exists(&from) => {
+                let prefix = components(&from);
+                let target = components(&to);
+                // A directory cannot be moved inside itself.
+                if target.len() > prefix.len() && target.starts_with(&prefix) {
+                    return Err(Error::path(&to, IoError::InvalidInput));
+                }
+                if self.metadata(&to).is_ok_and(|metadata| metadata.is_file()) {
+                    return Err(Error::path(&to, IoError::NotADirectory));
+                }
+                for entry in self.walk(&from) {
+                    let entry = entry?;
+                    if entry.is_file() {
+                        let mut path = target.clone();
+                        path.extend(components(&entry.path).into_iter().skip(prefix.len()));
+                        files.push((to_path(&path), self.read(&entry.path)?.to_vec()));
+                    }
+                }
+            }
+            Err(err) => return Err(err),
+        }
+
+        if self.metadata(&to).is_ok_and(|metadata| metadata.is_dir()) {
+            if !self.read_dir(&to)?.is_empty() {
+                return Err(Error::path(&to, IoError::DirectoryNotEmpty));
+            }
+            self.delete(&to)?;
+        }
+        self.delete(&from)?;
+        for (path, content) in files {
+            self.layer.write(&path, content)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use alloc::borrow::ToOwned;
+
+    fn base() -> Result<MemoryFileSystem> {
+        let mut fs = MemoryFileSystem::default();
+        fs.write("/src/main.rs", "fn main() {}")?;
+        fs.write("/src/lib.rs", "mod a;")?;
+        fs.write("/README.md", "readme")?;
+        Ok(fs)
+    }
+
+    #[test]
+    fn read_through() -> Result<()> {
+        let mut fs = OverlayFileSystem::new(base()?);
+        assert_eq!(fs.read("/src/main.rs")?, b"fn main() {}");
+
+        fs.write("/src/main.rs", "fn main() { run() }")?;
+        fs.write("/src/new.rs", "new")?;
+        assert_eq!(fs.read("/src/main.rs")?, b"fn main() { run() }");
+        assert_eq!(fs.read("/src/./new.rs")?, b"new");
+        assert_eq!(fs.base().read("/src/main.rs")?, b"fn main() {}");
+        assert!(!fs.base().exists("/src/new.rs"));
+        Ok(())
+    }
+
+    #[test]
+    fn whiteouts() -> Result<()> {
+        let mut fs = OverlayFileSystem::new(base()?);
+        fs.delete("/src")?;
+        assert!(!fs.exists("/src/main.rs"));
+        assert!(fs.read("/src/lib.rs").is_err());
+        assert!(fs.delete("/src/lib.rs").is_err());
+
+        fs.write("/src/lib.rs", "mod b;")?;
+        assert_eq!(fs.read("/src/lib.rs")?, b"mod b;");
+        assert!(!fs.exists("/src/main.rs"));
+
+        fs.rename("/README.md", "/docs/README.md")?;
+        assert!(!fs.exists("/README.md"));
+        assert_eq!(fs.read("/docs/README.md")?, b"readme");
+
+        fs.write("/tmp/a", "a")?;
+        fs.rename("/tmp", "/tmp2")?;
+        assert_eq!(fs.read("/tmp2/a")?, b"a");
+        assert!(!fs.exists("/tmp/a"));
+        Ok(())
+    }
+
+    #[test]
+    fn read_dir() -> Result<()> {
+        let mut fs = OverlayFileSystem::new(base()?);
+        fs.delete("/src/main.rs")?;
+        fs.write("/src/new.rs", "new")?;
+        fs.write("/docs/a.md", "a")?;
+
+        let names = |entries: Vec<DirEntry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.name).collect()
+        };
+        assert_eq!(names(fs.read_dir("/")?), ["README.md", "docs", "src"]);
+        assert_eq!(names(fs.read_dir("/src")?), ["lib.rs", "new.rs"]);
+        assert_eq!(names(fs.read_dir("/docs")?), ["a.md"]);
+
+        fs.delete("/src")?;
+        assert!(fs.read_dir("/src").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn metadata() -> Result<()> {
+        let mut fs = OverlayFileSystem::new(base()?);
+        fs.write("/src/main.rs", "fn main() { run() }")?;
+        assert_eq!(fs.metadata("/src/main.rs")?.len, 19);
+        assert_eq!(fs.metadata("/src/lib.rs")?.len, 6);
+        assert!(fs.metadata("/src")?.is_dir());
+
+        fs.delete("/README.md")?;
+        assert!(fs.metadata("/README.md").is_err());
+        fs.write("/docs/a.md", "a")?;
+        assert!(fs.metadata("/docs")?.is_dir());
+        Ok(())
+    }
+
+    #[test]
+    fn rename_base_dir() -> Result<()> {
+        let mut fs = OverlayFileSystem::new(base()?);
+        fs.write("/src/new.rs", "new")?;
+        fs.rename("/src", "/lib")?;
+
+        assert!(!fs.exists("/src"));
+        assert_eq!(fs.read("/lib/main.rs")?, b"fn main() {}");
+        assert_eq!(fs.read("/lib/lib.rs")?, b"mod a;");
+        assert_eq!(fs.read("/lib/new.rs")?, b"new");
+        assert!(fs.rename("/src", "/lib2").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rename_dir_errors() -> Result<()> {
+        let mut fs = OverlayFileSystem::new(base()?);
+        let err = fs.rename("/src", "/src/sub").unwrap_err();
+        assert_eq!(err.get_io(), Some(&IoError::InvalidInput));
+        let err = fs.rename("/src", "/README.md").unwrap_err();
+        assert_eq!(err.get_io(), Some(&IoError::NotADirectory));
+
+        assert!(fs.exists("/src/main.rs"));
+        assert_eq!(fs.read("/README.md")?, b"readme");
+        assert!(!fs.exists("/src/sub"));
+        assert!(!fs.is_dirty());
+        Ok(())
+    }
+
+    #[test]
+    fn rename_missing_keeps_destination() -> Result<()> {
+        let mut base = base()?;
+        base.write("/empty/a", "a")?;
+        base.delete("/empty/a")?;
+        let mut fs = OverlayFileSystem::new(base);
+
+        let err = fs.rename("/missing", "/empty").unwrap_err();
+        assert_eq!(err.get_io(), Some(&IoError::NotFound));
+        assert!(fs.metadata("/empty")?.is_dir());
+        assert!(!fs.is_dirty());
+        Ok(())
+    }
+
+    #[test]
+    fn rename_skips_empty_dirs() -> Result<()> {
+        let mut base = base()?;
+        base.write("/src/empty/a", "a")?;
+        base.delete("/src/empty/a")?;
+        let mut fs = OverlayFileSystem::new(base);
+        assert!(fs.metadata("/src/empty")?.is_dir());
+
+        fs.rename("/src", "/lib")?;
+        assert_eq!(fs.read("/lib/main.rs")?, b"fn main() {}");
+        assert!(!fs.exists("/lib/empty"));
+        assert!(!fs.exists("/src/empty"));
+        Ok(())
+    }
+
+    #[test]
+    fn commit() -> Result<()> {
+        let mut fs = OverlayFileSystem::new(base()?);
+        fs.delete("/src")?;
+        fs.write("/src/lib.rs", "mod b;")?;
+        fs.rename("/README.md", "/docs/README.md")?;
+        assert!(fs.is_dirty());
+        assert_eq!(
+            fs.deleted(),
+            [
+                OwnedPath::from("/README.md".to_owned()),
+                OwnedPath::from("/src".to_owned())
+            ]
+        );
+        assert_eq!(
+            fs.written(),
+            [
+                OwnedPath::from("/docs/README.md".to_owned()),
+                OwnedPath::from("/src/lib.rs".to_owned())
+            ]
+        );
+
+        fs.commit()?;
+        assert!(!fs.is_dirty());
+        let base = fs.into_base();
+        assert!(!base.exists("/README.md"));
+        assert!(!base.exists("/src/main.rs"));
+        assert_eq!(base.read("/src/lib.rs")?, b"mod b;");
+        assert_eq!(base.read("/docs/README.md")?, b"readme");
+        Ok(())
+    }
+
+    #[test]
+    fn error_contract() -> Result<()> {
+        crate::fs::check_error_contract(&mut OverlayFileSystem::new(MemoryFileSystem::default()))?;
+
+        let mut base = MemoryFileSystem::default();
+        base.write("/dir/file.txt", "content")?;
+        base.write("/dir/sub/a.txt", "a")?;
+        base.write("/other/b.txt", "b")?;
+        let mut fs = OverlayFileSystem::new(base);
+        fs.delete("/other")?;
+        crate::fs::check_error_contract(&mut fs)
+    }
+
+    #[test]
+    fn discard() -> Result<()> {
+        let mut fs = OverlayFileSystem::new(base()?);
+        fs.delete("/src/main.rs")?;
+        fs.write("/README.md", "changed")?;
+        fs.discard();
+
+        assert!(!fs.is_dirty());
+        assert!(fs.exists("/src/main.rs"));
+        assert_eq!(fs.read("/README.md")?, b"readme");
+        Ok(())
+    }
+}
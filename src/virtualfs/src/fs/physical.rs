@@ -0,0 +1,387 @@
+use crate::error::{Error, IoError, Result};
+use crate::fs::{DirEntry, EntryKind, FileSystem, Metadata};
+use crate::path::{Component, Path};
+use std::boxed::Box;
+use std::collections::btree_map::Entry as MapEntry;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::vec::Vec;
+
+/// The number of symbolic links followed when resolving a path, like most hosts.
+const MAX_LINKS: usize = 40;
+
+/// Resolve the `.` and `..` components of a host path, without following links.
+fn normalize(path: &std::path::Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> Error + '_ {
+    move |err| Error::path(path, IoError::from(err))
+}
+
+/// A FileSystem backed by a directory of the host. Paths are resolved relative to the
+/// root directory, whether they are absolute or not, and cannot point outside of it
+/// (using `..` or symbolic links).
+///
+/// Files are read once and cached, as `read` returns borrowed content. Changes made to
+/// the host directory by other means are not visible until the file is written, deleted
+/// or renamed through this file system.
+#[derive(Debug)]
+pub struct PhysicalFileSystem {
+    root: PathBuf,
+    cache: Mutex<BTreeMap<PathBuf, Box<[u8]>>>,
+}
+
+impl PhysicalFileSystem {
+    /// Create a file system rooted at an existing directory of the host.
+    pub fn new<P: AsRef<std::path::Path>>(root: P) -> Result<Self> {
+        let root = root
+            .as_ref()
+            .canonicalize()
+            .map_err(|err| Error::Io(IoError::from(err)))?;
+        if !root.is_dir() {
+            return Err(Error::Io(IoError::NotADirectory));
+        }
+
+        Ok(Self {
+            root,
+            cache: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// The (canonical) root directory on the host.
+    pub fn root(&self) -> &std::path::Path {
+        &self.root
+    }
+
+    /// Map a path to the host, refusing paths that escape the root directory.
+    fn host_path(&self, path: &Path) -> Result<PathBuf> {
+        let mut host = self.root.clone();
+        let mut depth = 0;
+        for component in path.iter() {
+            match component {
+                Component::RootDir | Component::CurDir => {}
+                Component::ParentDir if depth == 0 => {
+                    return Err(Error::path(path, IoError::PermissionDenied))
+                }
+                Component::ParentDir => {
+                    host.pop();
+                    depth -= 1;
+                }
+                Component::Named(name) => {
+                    host.push(name);
+                    depth += 1;
+                }
+            }
+        }
+
+        // Symbolic links can point outside of the root, so they are resolved as well.
+        self.check_links(path, &host, 0)?;
+        Ok(host)
+    }
+
+    /// Refuse host paths going through a symbolic link that points outside of the root,
+    /// including dangling links (which a write would create the target of).
+    fn check_links(&self, path: &Path, host: &std::path::Path, depth: usize) -> Result<()> {
+        let denied = || Error::path(path, IoError::PermissionDenied);
+        let relative = host.strip_prefix(&self.root).map_err(|_| denied())?;
+
+        let mut current = self.root.clone();
+        for component in relative.components() {
+            current.push(component);
+            let is_link = current
+                .symlink_metadata()
+                .map(|metadata| metadata.file_type().is_symlink())
+                .unwrap_or(false);
+            if !is_link {
+                continue;
+            }
+
+            match current.canonicalize() {
+                Ok(target) => current = target,
+                Err(_) if depth < MAX_LINKS => {
+                    let target = std::fs::read_link(&current).map_err(io_error(path))?;
+                    current.pop();
+                    current = normalize(&current.join(target));
+                    self.check_links(path, &current, depth + 1)?;
+                }
+                Err(_) => return Err(Error::path(path, IoError::InvalidInput)),
+            }
+            if !current.starts_with(&self.root) {
+                return Err(denied());
+            }
+        }
+        Ok(())
+    }
+
+    /// Refuse paths going through a file of the host.
+    fn check_parents(&self, path: &Path, host: &std::path::Path) -> Result<()> {
+        let mut parents = host.ancestors().skip(1);
+        if parents.any(|parent| parent.starts_with(&self.root) && parent.is_file()) {
+            Err(Error::path(path, IoError::NotADirectory))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn cache_mut(&mut self) -> &mut BTreeMap<PathBuf, Box<[u8]>> {
+        self.cache.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl FileSystem for PhysicalFileSystem {
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<&[u8]> {
+        let host = self.host_path(path.as_ref())?;
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let content: *const [u8] = match cache.entry(host) {
+            MapEntry::Occupied(entry) => &**entry.into_mut(),
+            MapEntry::Vacant(entry) => {
+                let content = std::fs::read(entry.key()).map_err(io_error(path.as_ref()))?;
+                &**entry.insert(content.into_boxed_slice())
+            }
+        };
+
+        // SAFETY: the content is boxed, so it does not move when the cache changes, and it
+        // is only dropped by methods taking `&mut self`, which cannot be called while the
+        // returned reference is alive.
+        Ok(unsafe { &*content })
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.host_path(path.as_ref())
+            .map(|host| host.exists())
+            .unwrap_or(false)
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Vec<DirEntry>> {
+        let path = path.as_ref();
+        let host = self.host_path(path)?;
+        let mut entries = Vec::new();
+
+        for entry in std::fs::read_dir(&host).map_err(io_error(path))? {
+            let entry = entry.map_err(io_error(path))?;
+            let name = entry
+                .file_name()
+                .into_string()
+                .map_err(|_| Error::path(path, IoError::InvalidData))?;
+            let kind = if entry.path().is_dir() {
+                EntryKind::Directory
+            } else {
+                EntryKind::File
+            };
+            entries.push(DirEntry::new(path, &name, kind));
+        }
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    /// The metadata of an entry of the host. Content hashes are not computed.
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Metadata> {
+        let path = path.as_ref();
+        let metadata = std::fs::metadata(self.host_path(path)?).map_err(io_error(path))?;
+        let since_epoch = |time: std::io::Result<SystemTime>| {
+            time.ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        };
+
+        Ok(Metadata {
+            kind: if metadata.is_dir() {
+                EntryKind::Directory
+            } else {
+                EntryKind::File
+            },
+            len: if metadata.is_dir() { 0 } else { metadata.len() },
+            created: since_epoch(metadata.created()),
+            modified: since_epoch(metadata.modified()),
+            readonly: metadata.permissions().readonly(),
+            hash: None,
+        })
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&mut self, path: P, content: C) -> Result<()> {
+        let path = path.as_ref();
+        let host = self.host_path(path)?;
+        self.check_parents(path, &host)?;
+        if host.is_dir() {
+            return Err(Error::path(path, IoError::IsADirectory));
+        }
+        if let Some(parent) = host.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error(path))?;
+        }
+        self.cache_mut().remove(&host);
+        std::fs::write(&host, content).map_err(io_error(path))
+    }
+
+    fn delete<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let host = self.host_path(path)?;
+        if host == self.root {
+            return Err(Error::path(path, IoError::PermissionDenied));
+        }
+        let metadata = std::fs::symlink_metadata(&host).map_err(io_error(path))?;
+
+        self.cache_mut()
+            .retain(|cached, _| !cached.starts_with(&host));
+        if metadata.is_dir() {
+            std::fs::remove_dir_all(&host).map_err(io_error(path))
+        } else {
+            std::fs::remove_file(&host).map_err(io_error(path))
+        }
+    }
+
+    /// Rename an entry, replacing a file or an empty directory at the destination.
+    fn rename<From: AsRef<Path>, To: AsRef<Path>>(&mut self, from: From, to: To) -> Result<()> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        let from_host = self.host_path(from)?;
+        let to_host = self.host_path(to)?;
+        if from_host == self.root {
+            return Err(Error::path(from, IoError::PermissionDenied));
+        }
+        if to_host == self.root {
+            return Err(Error::path(to, IoError::PermissionDenied));
+        }
+
+        std::fs::symlink_metadata(&from_host).map_err(io_error(from))?;
+        self.check_parents(to, &to_host)?;
+        if let Ok(mut entries) = std::fs::read_dir(&to_host) {
+            if entries.next().is_some() {
+                return Err(Error::path(to, IoError::DirectoryNotEmpty));
+            }
+        }
+        if let Some(parent) = to_host.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error(to))?;
+        }
+
+        self.cache_mut()
+            .retain(|cached, _| !cached.starts_with(&from_host) && !cached.starts_with(&to_host));
+        std::fs::rename(&from_host, &to_host).map_err(io_error(from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use std::format;
+
+    /// A temporary directory, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("virtualfs-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn base() -> Result<()> {
+        let dir = TempDir::new("base");
+        let mut fs = PhysicalFileSystem::new(&dir.0)?;
+        fs.write("/a/b/hello.txt", "blue")?;
+        assert_eq!(std::fs::read(dir.0.join("a/b/hello.txt")).unwrap(), b"blue");
+        assert_eq!(fs.read("/a/b/hello.txt")?, b"blue");
+        assert_eq!(fs.read("a/./b/../b/hello.txt")?, b"blue");
+        assert!(fs.exists("/a/b"));
+        assert!(!fs.exists("/a/c"));
+
+        fs.write("/a/b/hello.txt", "red")?;
+        assert_eq!(fs.read("/a/b/hello.txt")?, b"red");
+
+        let metadata = fs.metadata("/a/b/hello.txt")?;
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len, 3);
+        assert!(metadata.modified.is_some());
+        assert_eq!(metadata.hash, None);
+        assert!(fs.metadata("/a/b")?.is_dir());
+        assert!(fs.metadata("/").is_ok());
+        assert!(fs.metadata("/a/c").is_err());
+
+        fs.write("/a/a.txt", "")?;
+        let entries = fs.read_dir("/a")?;
+        assert_eq!(
+            entries
+                .iter()
+                .map(|e| (AsRef::<str>::as_ref(e.path.as_path()), e.kind))
+                .collect::<Vec<(&str, EntryKind)>>(),
+            [
+                ("/a/a.txt", EntryKind::File),
+                ("/a/b", EntryKind::Directory)
+            ]
+        );
+
+        fs.rename("/a/b", "/c")?;
+        assert!(!fs.exists("/a/b/hello.txt"));
+        assert_eq!(fs.read("/c/hello.txt")?, b"red");
+
+        fs.delete("/c")?;
+        assert!(!fs.exists("/c/hello.txt"));
+        let err = std::io::Error::from(fs.read("/c/hello.txt").unwrap_err());
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        Ok(())
+    }
+
+    #[test]
+    fn error_contract() -> Result<()> {
+        let dir = TempDir::new("error_contract");
+        crate::fs::check_error_contract(&mut PhysicalFileSystem::new(&dir.0)?)
+    }
+
+    #[test]
+    fn escape() -> Result<()> {
+        let dir = TempDir::new("escape");
+        std::fs::create_dir_all(dir.0.join("root")).unwrap();
+        std::fs::write(dir.0.join("secret.txt"), "secret").unwrap();
+        let mut fs = PhysicalFileSystem::new(dir.0.join("root"))?;
+
+        let denied = Some(&IoError::PermissionDenied);
+        assert_eq!(fs.read("/../secret.txt").unwrap_err().get_io(), denied);
+        assert_eq!(fs.read("a/../../secret.txt").unwrap_err().get_io(), denied);
+        assert_eq!(fs.write("/../other.txt", "").unwrap_err().get_io(), denied);
+        assert_eq!(fs.delete("/").unwrap_err().get_io(), denied);
+        assert!(!fs.exists("/../secret.txt"));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&dir.0, dir.0.join("root/link")).unwrap();
+            assert_eq!(fs.read("/link/secret.txt").unwrap_err().get_io(), denied);
+            assert_eq!(
+                fs.write("/link/new/file.txt", "").unwrap_err().get_io(),
+                denied
+            );
+            assert!(!dir.0.join("new").exists());
+
+            std::os::unix::fs::symlink("../outside.txt", dir.0.join("root/dangling")).unwrap();
+            assert_eq!(fs.write("/dangling", "").unwrap_err().get_io(), denied);
+            assert!(!dir.0.join("outside.txt").exists());
+
+            std::os::unix::fs::symlink("inside.txt", dir.0.join("root/inside")).unwrap();
+            fs.write("/inside", "inside")?;
+            assert_eq!(fs.read("/inside.txt")?, b"inside");
+        }
+        Ok(())
+    }
+}
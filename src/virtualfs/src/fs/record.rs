@@ -0,0 +1,424 @@
+use crate::error::Result;
+use crate::fs::{DirEntry, FileSystem, Metadata};
+use crate::path::{components, OwnedPath, Path};
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A change made to a FileSystem.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Action {
+    Create { path: OwnedPath, content: Vec<u8> },
+    Overwrite { path: OwnedPath, content: Vec<u8> },
+    Rename { from: OwnedPath, to: OwnedPath },
+    Delete { path: OwnedPath },
+}
+
+impl Action {
+    /// Apply this action to a FileSystem.
+    pub fn apply<Fs: FileSystem>(&self, fs: &mut Fs) -> Result<()> {
+        match self {
+            Action::Create { path, content } | Action::Overwrite { path, content } => {
+                fs.write(path, content)
+            }
+            Action::Rename { from, to } => fs.rename(from, to),
+            Action::Delete { path } => fs.delete(path),
+        }
+    }
+
+    fn paths(&self) -> (&Path, Option<&Path>) {
+        match self {
+            Action::Create { path, .. }
+            | Action::Overwrite { path, .. }
+            | Action::Delete { path } => (path, None),
+            Action::Rename { from, to } => (from, Some(to)),
+        }
+    }
+}
+
+/// A FileSystem wrapper that records every change made through it, in order.
+pub struct RecordingFileSystem<Fs: FileSystem> {
+    inner: Fs,
+    actions: Vec<Action>,
+}
+
+impl<Fs: FileSystem> RecordingFileSystem<Fs> {
+    pub fn new(inner: Fs) -> Self {
+        Self {
+            inner,
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn inner(&self) -> &Fs {
+        &self.inner
+    }
+
+    /// The actions recorded, in the order they were made.
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    /// Return the wrapped FileSystem and the actions recorded.
+    pub fn into_parts(self) -> (Fs, Vec<Action>) {
+        (self.inner, self.actions)
+    }
+}
+
+impl<Fs: FileSystem> FileSystem for RecordingFileSystem<Fs> {
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<&[u8]> {
+        self.inner.read(path)
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Vec<DirEntry>> {
+        self.inner.read_dir(path)
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Metadata> {
+        self.inner.metadata(path)
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&mut self, path: P, content: C) -> Result<()> {
+        let path = path.as_ref().to_owned();
+        let existed = self.inner.exists(&path);
+        self.inner.write(&path, content.as_ref())?;
+
+        let content = content.as_ref().to_vec();
+        self.actions.push(if existed {
+            Action::Overwrite { path, content }
+        } else {
+            Action::Create { path, content }
+        });
+        Ok(())
+    }
+
+    fn delete<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.inner.delete(path.as_ref())?;
+        self.actions.push(Action::Delete {
+            path: path.as_ref().to_owned(),
+        });
+        Ok(())
+    }
+
+    fn rename<From: AsRef<Path>, To: AsRef<Path>>(&mut self, from: From, to: To) -> Result<()> {
+        self.inner.rename(from.as_ref(), to.as_ref())?;
+        self.actions.push(Action::Rename {
+            from: from.as_ref().to_owned(),
+            to: to.as_ref().to_owned(),
+        });
+        Ok(())
+    }
+}
+
+/// Whether one path is the other, or contains the other.
+fn is_related(a: &[String], b: &[String]) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// The index of the last action on exactly this path, unless an action on a related path
+/// (a parent, a child, or a rename) happened since.
+fn last_action_on(actions: &[Action], path: &Path) -> Option<usize> {
+    let path = components(path);
+    for (i, action) in actions.iter().enumerate().rev() {
+        match action.paths() {
+            (p, None) if components(p) == path => return Some(i),
+            (p, None) if !is_related(&components(p), &path) => {}
+            (from, Some(to))
+                if !is_related(&components(from), &path) && !is_related(&components(to), &path) => {
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Remove the redundant actions of a list, without changing its result. A file created
+/// then deleted is dropped, and a file written multiple times is written once. A path
+/// deleted then created again is only overwritten when it is known to be a file, as it
+/// could be a directory.
+pub fn optimize_actions(actions: &[Action]) -> Vec<Action> {
+    let mut result: Vec<Action> = Vec::with_capacity(actions.len());
+    // Whether each action of the result is known to apply to a file.
+    let mut is_file: Vec<bool> = Vec::with_capacity(actions.len());
+
+    for action in actions {
+        let last = match action {
+            Action::Rename { .. } => None,
+            Action::Create { path, .. }
+            | Action::Overwrite { path, .. }
+            | Action::Delete { path } => last_action_on(&result, path),
+        };
+        let i = match last {
+            Some(i) => i,
+            None => {
+                result.push(action.clone());
+                is_file.push(!matches!(action, Action::Delete { .. }));
+                continue;
+            }
+        };
+
+        match (&mut result[i], action) {
+            (
+                Action::Create {
+                    content: previous, ..
+                }
+                | Action::Overwrite {
+                    content: previous, ..
+                },
+                Action::Create { content, .. } | Action::Overwrite { content, .. },
+            ) => *previous = content.clone(),
+            (previous @ Action::Delete { .. }, Action::Create { path, content }) if is_file[i] => {
+                *previous = Action::Overwrite {
+                    path: path.clone(),
+                    content: content.clone(),
+                }
+            }
+            (Action::Create { .. }, Action::Delete { .. }) => {
+                result.remove(i);
+                is_file.remove(i);
+            }
+            (previous @ Action::Overwrite { .. }, Action::Delete { .. }) => {
+                *previous = action.clone()
+            }
+            _ => {
+                result.push(action.clone());
+                is_file.push(!matches!(action, Action::Delete { .. }));
+            }
+        }
+    }
+
+    result
+}
+
+/// The action undoing an action that is about to be applied to a FileSystem.
+fn undo_action<Fs: FileSystem>(fs: &Fs, action: &Action) -> Vec<Action> {
+    let snapshot = |path: &OwnedPath| {
+        fs.read(path).ok().map(|content| Action::Overwrite {
+            path: path.clone(),
+            content: content.to_vec(),
+        })
+    };
+
+    match action {
+        Action::Create { path, .. } | Action::Overwrite { path, .. } => match snapshot(path) {
+            Some(undo) => vec![undo],
+            None => vec![Action::Delete { path: path.clone() }],
+        },
+        Action::Delete { path } => match snapshot(path) {
+            Some(undo) => vec![undo],
+            // A directory; restore every file under it.
+            None => fs
+                .walk(path)
+                .filter_map(|entry| entry.ok().filter(DirEntry::is_file))
+                .filter_map(|entry| snapshot(&entry.path))
+                .collect(),
+        },
+        Action::Rename { from, to } => {
+            // Renaming back, then restoring what the rename replaced.
+            let mut undo = vec![Action::Rename {
+                from: to.clone(),
+                to: from.clone(),
+            }];
+            undo.extend(snapshot(to));
+            undo
+        }
+    }
+}
+
+/// Apply a list of actions to a FileSystem, as a transaction. If an action fails, the
+/// actions applied before it are undone and the error is returned.
+///
+/// Undoing restores the files of deleted directories, but not empty directories.
+pub fn apply_actions<Fs: FileSystem>(actions: &[Action], fs: &mut Fs) -> Result<()> {
+    let mut undo = Vec::new();
+
+    for action in actions {
+        let undo_this = undo_action(fs, action);
+        if let Err(err) = action.apply(fs) {
+            for action in undo.iter().rev() {
+                let _ = Action::apply(action, fs);
+            }
+            return Err(err);
+        }
+        undo.extend(undo_this.into_iter().rev());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use crate::fs::MemoryFileSystem;
+
+    fn path(p: &str) -> OwnedPath {
+        OwnedPath::from(p.to_owned())
+    }
+
+    #[test]
+    fn record() -> Result<()> {
+        let mut fs = RecordingFileSystem::new(MemoryFileSystem::default());
+        fs.write("/a.txt", "a")?;
+        fs.write("/a.txt", "b")?;
+        fs.rename("/a.txt", "/b.txt")?;
+        fs.delete("/b.txt")?;
+        assert!(fs.delete("/b.txt").is_err());
+
+        assert_eq!(
+            fs.actions(),
+            [
+                Action::Create {
+                    path: path("/a.txt"),
+                    content: b"a".to_vec()
+                },
+                Action::Overwrite {
+                    path: path("/a.txt"),
+                    content: b"b".to_vec()
+                },
+                Action::Rename {
+                    from: path("/a.txt"),
+                    to: path("/b.txt")
+                },
+                Action::Delete {
+                    path: path("/b.txt")
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn optimize() {
+        let create = |p: &str, c: &str| Action::Create {
+            path: path(p),
+            content: c.as_bytes().to_vec(),
+        };
+        let overwrite = |p: &str, c: &str| Action::Overwrite {
+            path: path(p),
+            content: c.as_bytes().to_vec(),
+        };
+        let delete = |p: &str| Action::Delete { path: path(p) };
+        let rename = |from: &str, to: &str| Action::Rename {
+            from: path(from),
+            to: path(to),
+        };
+
+        assert_eq!(
+            optimize_actions(&[create("/a", "1"), overwrite("/b", "2"), delete("/a")]),
+            [overwrite("/b", "2")]
+        );
+        assert_eq!(
+            optimize_actions(&[
+                overwrite("/a", "1"),
+                create("/b", "2"),
+                overwrite("/a", "3")
+            ]),
+            [overwrite("/a", "3"), create("/b", "2")]
+        );
+        assert_eq!(
+            optimize_actions(&[create("/a", "1"), overwrite("/a", "2")]),
+            [create("/a", "2")]
+        );
+        assert_eq!(
+            optimize_actions(&[overwrite("/a", "1"), delete("/a")]),
+            [delete("/a")]
+        );
+        assert_eq!(
+            optimize_actions(&[overwrite("/a", "0"), delete("/a"), create("/a", "1")]),
+            [overwrite("/a", "1")]
+        );
+
+        // Renames and changes to parents or children are kept in order.
+        let actions = [create("/a", "1"), rename("/a", "/b"), overwrite("/a", "2")];
+        assert_eq!(optimize_actions(&actions), actions);
+        let actions = [create("/d/a", "1"), delete("/d"), create("/d/a", "2")];
+        assert_eq!(optimize_actions(&actions), actions);
+    }
+
+    #[test]
+    fn optimize_deleted_directory() -> Result<()> {
+        let actions = [
+            Action::Delete { path: path("/d") },
+            Action::Create {
+                path: path("/d"),
+                content: b"d".to_vec(),
+            },
+        ];
+        // The deleted path may be a directory, which cannot be overwritten.
+        assert_eq!(optimize_actions(&actions), actions);
+
+        let mut target = MemoryFileSystem::default();
+        target.write("/d/a.txt", "a")?;
+        apply_actions(&optimize_actions(&actions), &mut target)?;
+        assert_eq!(target.read("/d")?, b"d");
+        Ok(())
+    }
+
+    #[test]
+    fn apply() -> Result<()> {
+        let mut recording = RecordingFileSystem::new(MemoryFileSystem::default());
+        recording.write("/a.txt", "a")?;
+        recording.write("/b.txt", "b")?;
+        recording.rename("/b.txt", "/c.txt")?;
+        let (_, actions) = recording.into_parts();
+
+        let mut target = MemoryFileSystem::default();
+        target.write("/a.txt", "old")?;
+        apply_actions(&actions, &mut target)?;
+        assert_eq!(target.read("/a.txt")?, b"a");
+        assert_eq!(target.read("/c.txt")?, b"b");
+        assert!(!target.exists("/b.txt"));
+        Ok(())
+    }
+
+    #[test]
+    fn error_contract() -> Result<()> {
+        let mut fs = RecordingFileSystem::new(MemoryFileSystem::default());
+        crate::fs::check_error_contract(&mut fs)?;
+        assert_eq!(fs.actions().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn rollback() -> Result<()> {
+        let mut target = MemoryFileSystem::default();
+        target.write("/a.txt", "old")?;
+        target.write("/d.txt", "d")?;
+        target.write("/dir/a/e.txt", "e")?;
+
+        let actions = [
+            Action::Overwrite {
+                path: path("/a.txt"),
+                content: b"new".to_vec(),
+            },
+            Action::Create {
+                path: path("/b.txt"),
+                content: b"b".to_vec(),
+            },
+            Action::Delete {
+                path: path("/d.txt"),
+            },
+            Action::Delete { path: path("/dir") },
+            Action::Rename {
+                from: path("/b.txt"),
+                to: path("/c.txt"),
+            },
+            Action::Delete {
+                path: path("/missing.txt"),
+            },
+        ];
+        assert!(apply_actions(&actions, &mut target).is_err());
+
+        assert_eq!(target.read("/a.txt")?, b"old");
+        assert_eq!(target.read("/d.txt")?, b"d");
+        assert_eq!(target.read("/dir/a/e.txt")?, b"e");
+        assert!(!target.exists("/b.txt"));
+        assert!(!target.exists("/c.txt"));
+        Ok(())
+    }
+}
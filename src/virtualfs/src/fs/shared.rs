@@ -0,0 +1,477 @@
+use crate::error::{Error, IoError, Result};
+use crate::fs::{content_hash, Clock, DirEntry, EntryKind, Metadata, SystemClock};
+use crate::path::{components, to_path, OwnedPath, Path};
+use std::boxed::Box;
+use std::collections::BTreeMap;
+use std::string::String;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+use std::vec::Vec;
+
+type Files = BTreeMap<Vec<String>, Arc<SharedFile>>;
+
+struct FileState {
+    content: Arc<[u8]>,
+    modified: Duration,
+    hash: u64,
+}
+
+struct SharedFile {
+    created: Duration,
+    state: RwLock<FileState>,
+}
+
+impl SharedFile {
+    fn state(&self) -> RwLockReadGuard<'_, FileState> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn state_mut(&self) -> RwLockWriteGuard<'_, FileState> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+struct Inner {
+    files: RwLock<Files>,
+    clock: Box<dyn Clock>,
+}
+
+/// The files under a directory (or the file itself), as a range of the map.
+fn under<'a>(
+    files: &'a Files,
+    key: &'a [String],
+) -> impl Iterator<Item = (&'a Vec<String>, &'a Arc<SharedFile>)> {
+    files
+        .range(key.to_vec()..)
+        .take_while(move |(path, _)| path.starts_with(key))
+}
+
+/// Refuse paths going through a file.
+fn check_parents(files: &Files, path: &Path, key: &[String]) -> Result<()> {
+    if (1..key.len()).any(|i| files.contains_key(&key[..i])) {
+        Err(Error::path(path, IoError::NotADirectory))
+    } else {
+        Ok(())
+    }
+}
+
+/// A thread-safe in-memory file system, for tasks reading and writing one tree in parallel.
+///
+/// Unlike [`FileSystem`](crate::fs::FileSystem), every method takes `&self`, and `read`
+/// returns reference-counted content, so readers never hold a lock while using it. The
+/// tree is only locked exclusively to add or remove files; each file has its own lock for
+/// its content. Cloning the file system shares the same tree.
+///
+/// Directories only exist as the parents of files.
+#[derive(Clone)]
+pub struct SharedMemoryFileSystem {
+    inner: Arc<Inner>,
+}
+
+impl Default for SharedMemoryFileSystem {
+    fn default() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl SharedMemoryFileSystem {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create an empty file system timestamping its files with a clock.
+    pub fn with_clock<C: Clock + 'static>(clock: C) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                files: RwLock::new(BTreeMap::new()),
+                clock: Box::new(clock),
+            }),
+        }
+    }
+
+    fn files(&self) -> RwLockReadGuard<'_, Files> {
+        self.inner
+            .files
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn files_mut(&self) -> RwLockWriteGuard<'_, Files> {
+        self.inner
+            .files
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn file(&self, key: &[String]) -> Option<Arc<SharedFile>> {
+        self.files().get(key).cloned()
+    }
+
+    fn is_dir(&self, key: &[String]) -> bool {
+        under(&self.files(), key).any(|(path, _)| path.len() > key.len())
+    }
+
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> Result<Arc<[u8]>> {
+        let path = path.as_ref();
+        let key = components(path);
+        match self.file(&key) {
+            Some(file) => Ok(file.state().content.clone()),
+            None if self.is_dir(&key) => Err(Error::path(path, IoError::IsADirectory)),
+            None => {
+                check_parents(&self.files(), path, &key)?;
+                Err(Error::path(path, IoError::NotFound))
+            }
+        }
+    }
+
+    pub fn exists<P: AsRef<Path>>(&self, path: P) -> bool {
+        under(&self.files(), &components(path.as_ref()))
+            .next()
+            .is_some()
+    }
+
+    /// The entries of a directory, sorted by name.
+    pub fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Vec<DirEntry>> {
+        let path = path.as_ref();
+        let key = components(path);
+        let files = self.files();
+        if files.contains_key(&key) {
+            return Err(Error::path(path, IoError::NotADirectory));
+        }
+
+        let mut entries = BTreeMap::new();
+        for (file, _) in under(&files, &key) {
+            let kind = if file.len() == key.len() + 1 {
+                EntryKind::File
+            } else {
+                EntryKind::Directory
+            };
+            entries.insert(&file[key.len()], kind);
+        }
+
+        if entries.is_empty() && !path.is_root() {
+            check_parents(&files, path, &key)?;
+            return Err(Error::path(path, IoError::NotFound));
+        }
+        Ok(entries
+            .into_iter()
+            .map(|(name, kind)| DirEntry::new(path, name, kind))
+            .collect())
+    }
+
+    /// The metadata of an entry. Directories have no timestamps.
+    pub fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Metadata> {
+        let path = path.as_ref();
+        let key = components(path);
+        if let Some(file) = self.file(&key) {
+            let state = file.state();
+            return Ok(Metadata {
+                kind: EntryKind::File,
+                len: state.content.len() as u64,
+                created: Some(file.created),
+                modified: Some(state.modified),
+                readonly: false,
+                hash: Some(state.hash),
+            });
+        }
+
+        if self.is_dir(&key) || path.is_root() {
+            Ok(Metadata {
+                kind: EntryKind::Directory,
+                len: 0,
+                created: None,
+                modified: None,
+                readonly: false,
+                hash: None,
+            })
+        } else {
+            Err(Error::path(path, IoError::NotFound))
+        }
+    }
+
+    /// Write a file, creating it (and its parents) if needed. When the file already exists,
+    /// the tree is only locked for reading, so that the file cannot be renamed or deleted
+    /// while it is written.
+    pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, content: C) -> Result<()> {
+        let path = path.as_ref();
+        let key = components(path);
+        let content = content.as_ref();
+        let now = self.inner.clock.now();
+        let state = FileState {
+            hash: content_hash(content),
+            content: Arc::from(content),
+            modified: now,
+        };
+
+        if let Some(file) = self.files().get(&key) {
+            *file.state_mut() = state;
+            return Ok(());
+        }
+
+        let mut files = self.files_mut();
+        check_parents(&files, path, &key)?;
+        if under(&files, &key).any(|(file, _)| file.len() > key.len()) {
+            return Err(Error::path(path, IoError::IsADirectory));
+        }
+
+        // The file may have been created since the tree was locked for reading.
+        match files.get(&key) {
+            Some(file) => *file.state_mut() = state,
+            None => {
+                let file = SharedFile {
+                    created: now,
+                    state: RwLock::new(state),
+                };
+                files.insert(key, Arc::new(file));
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete a file, or a directory with all its files.
+    pub fn delete<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let key = components(path);
+        let mut files = self.files_mut();
+        let deleted = under(&files, &key)
+            .map(|(file, _)| file.clone())
+            .collect::<Vec<_>>();
+        if deleted.is_empty() {
+            return Err(Error::path(path, IoError::NotFound));
+        }
+
+        for file in deleted {
+            files.remove(&file);
+        }
+        Ok(())
+    }
+
+    /// Rename a file or a directory, replacing a file at the destination.
+    pub fn rename<From: AsRef<Path>, To: AsRef<Path>>(&self, from: From, to: To) -> Result<()> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        let from_key = components(from);
+        let to_key = components(to);
+        let mut files = self.files_mut();
+
+        let moved = under(&files, &from_key)
+            .map(|(file, _)| file.clone())
+            .collect::<Vec<_>>();
+        if moved.is_empty() {
+            return Err(Error::path(from, IoError::NotFound));
+        }
+        check_parents(&files, to, &to_key)?;
+        if !files.contains_key(&from_key) && files.contains_key(&to_key) {
+            return Err(Error::path(to, IoError::NotADirectory));
+        }
+        if under(&files, &to_key).any(|(file, _)| file.len() > to_key.len()) {
+            return Err(Error::path(to, IoError::DirectoryNotEmpty));
+        }
+        if to_key.starts_with(&from_key) && to_key.len() > from_key.len() {
+            return Err(Error::path(to, IoError::InvalidInput));
+        }
+
+        for path in moved {
+            if let Some(file) = files.remove(&path) {
+                let mut target = to_key.clone();
+                target.extend_from_slice(&path[from_key.len()..]);
+                files.insert(target, file);
+            }
+        }
+        Ok(())
+    }
+
+    /// The paths of every file, sorted.
+    pub fn paths(&self) -> Vec<OwnedPath> {
+        self.files().keys().map(|key| to_path(key)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::ToOwned;
+    use std::format;
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use std::thread;
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedMemoryFileSystem>();
+    }
+
+    #[test]
+    fn base() -> Result<()> {
+        let fs = SharedMemoryFileSystem::new();
+        fs.write("/a/b/hello.txt", "blue")?;
+        assert_eq!(&*fs.read("/a/b/hello.txt")?, b"blue");
+        assert!(fs.exists("/a/b"));
+        assert!(fs.metadata("/a")?.is_dir());
+        assert_eq!(
+            fs.read_dir("/a")?[0].path,
+            to_path(&components(Path::new("/a/b")))
+        );
+        assert!(fs.read("/a").is_err());
+        assert!(fs.write("/a", "").is_err());
+        assert!(fs.write("/a/b/hello.txt/c", "").is_err());
+
+        fs.rename("/a", "/c")?;
+        assert!(!fs.exists("/a"));
+        assert_eq!(&*fs.read("/c/b/hello.txt")?, b"blue");
+        assert!(fs.rename("/c", "/c/d").is_err());
+        fs.write("/d/e.txt", "e")?;
+        assert!(fs.rename("/c/b/hello.txt", "/d").is_err());
+        fs.delete("/d")?;
+
+        fs.delete("/c")?;
+        assert!(!fs.exists("/c/b/hello.txt"));
+        assert!(fs.delete("/c").is_err());
+        assert_eq!(fs.read_dir("/")?, []);
+        Ok(())
+    }
+
+    /// The same contract as `check_error_contract`, which needs a FileSystem.
+    #[test]
+    fn error_contract() -> Result<()> {
+        fn error<T>(result: Result<T>) -> Option<(IoError, String)> {
+            let err = result.err()?;
+            Some((*err.get_io()?, err.get_path()?.as_str().to_owned()))
+        }
+        let expected = |kind: IoError, path: &str| Some((kind, path.to_owned()));
+
+        let fs = SharedMemoryFileSystem::new();
+        fs.write("/dir/file.txt", "content")?;
+        fs.write("/dir/sub/a.txt", "a")?;
+
+        let not_found = expected(IoError::NotFound, "/missing");
+        assert_eq!(error(fs.read("/missing")), not_found);
+        assert_eq!(error(fs.read_dir("/missing")), not_found);
+        assert_eq!(error(fs.metadata("/missing")), not_found);
+        assert_eq!(error(fs.delete("/missing")), not_found);
+        assert_eq!(error(fs.rename("/missing", "/other")), not_found);
+
+        let is_a_dir = expected(IoError::IsADirectory, "/dir");
+        assert_eq!(error(fs.read("/dir")), is_a_dir);
+        assert_eq!(error(fs.write("/dir", "")), is_a_dir);
+
+        let not_a_dir = expected(IoError::NotADirectory, "/dir/file.txt/a");
+        assert_eq!(error(fs.read("/dir/file.txt/a")), not_a_dir);
+        assert_eq!(error(fs.write("/dir/file.txt/a", "")), not_a_dir);
+        assert_eq!(
+            error(fs.rename("/dir/file.txt", "/dir/file.txt/a")),
+            not_a_dir
+        );
+        assert_eq!(
+            error(fs.read_dir("/dir/file.txt")),
+            expected(IoError::NotADirectory, "/dir/file.txt")
+        );
+        assert_eq!(
+            error(fs.rename("/dir/file.txt", "/dir/sub")),
+            expected(IoError::DirectoryNotEmpty, "/dir/sub")
+        );
+        fs.write("/d.txt", "d")?;
+        assert_eq!(
+            error(fs.rename("/dir", "/d.txt")),
+            expected(IoError::NotADirectory, "/d.txt")
+        );
+
+        assert_eq!(&*fs.read("/dir/file.txt")?, b"content");
+        assert_eq!(&*fs.read("/dir/sub/a.txt")?, b"a");
+        assert_eq!(&*fs.read("/d.txt")?, b"d");
+        Ok(())
+    }
+
+    #[test]
+    fn metadata() -> Result<()> {
+        let time = Arc::new(AtomicU64::new(1));
+        let clock = {
+            let time = time.clone();
+            move || Duration::from_secs(time.load(Ordering::SeqCst))
+        };
+        let fs = SharedMemoryFileSystem::with_clock(clock);
+        fs.write("/a.txt", "a")?;
+        time.store(2, Ordering::SeqCst);
+        fs.write("/a.txt", "ab")?;
+
+        let metadata = fs.metadata("/a.txt")?;
+        assert_eq!(metadata.len, 2);
+        assert_eq!(metadata.created, Some(Duration::from_secs(1)));
+        assert_eq!(metadata.modified, Some(Duration::from_secs(2)));
+        assert_eq!(metadata.hash, Some(content_hash(b"ab")));
+        Ok(())
+    }
+
+    #[test]
+    fn readers_keep_content() -> Result<()> {
+        let fs = SharedMemoryFileSystem::new();
+        fs.write("/a.txt", "old")?;
+        let old = fs.read("/a.txt")?;
+        fs.write("/a.txt", "new")?;
+        assert_eq!(&*old, b"old");
+        assert_eq!(&*fs.read("/a.txt")?, b"new");
+        Ok(())
+    }
+
+    #[test]
+    fn write_while_renaming() -> Result<()> {
+        let fs = SharedMemoryFileSystem::new();
+        let done = Arc::new(AtomicBool::new(false));
+        let writer = {
+            let (fs, done) = (fs.clone(), done.clone());
+            thread::spawn(move || -> Result<()> {
+                for i in 0u64.. {
+                    if done.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    fs.write("/a.txt", i.to_le_bytes())?;
+                }
+                Ok(())
+            })
+        };
+
+        // Nothing writes to /b.txt, so its content cannot change once it is renamed.
+        let mut renamed = 0;
+        while renamed < 200 {
+            if fs.rename("/a.txt", "/b.txt").is_err() {
+                continue;
+            }
+            let content = fs.read("/b.txt")?;
+            thread::yield_now();
+            assert_eq!(fs.read("/b.txt")?, content);
+            fs.delete("/b.txt")?;
+            renamed += 1;
+        }
+        done.store(true, Ordering::Relaxed);
+        writer.join().unwrap()?;
+        assert!(!fs.exists("/b.txt"));
+        Ok(())
+    }
+
+    #[test]
+    fn threads() -> Result<()> {
+        let fs = SharedMemoryFileSystem::new();
+        fs.write("/shared.txt", "0")?;
+
+        let handles = (0..8)
+            .map(|i| {
+                let fs = fs.clone();
+                thread::spawn(move || -> Result<()> {
+                    for j in 0..100 {
+                        fs.write(format!("/{}/{}.txt", i, j).as_str(), format!("{}", j))?;
+                        fs.write("/shared.txt", format!("{}", i))?;
+                        assert!(!fs.read("/shared.txt")?.is_empty());
+                    }
+                    Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap()?;
+        }
+
+        assert_eq!(fs.paths().len(), 801);
+        assert_eq!(fs.read_dir("/")?.len(), 9);
+        assert_eq!(&*fs.read("/7/99.txt")?, b"99");
+        Ok(())
+    }
+}
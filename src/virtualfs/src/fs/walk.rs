@@ -0,0 +1,188 @@
+use crate::error::Result;
+use crate::fs::FileSystem;
+use crate::path::{components, to_path, OwnedPath, Path};
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The kind of an entry of a FileSystem.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum EntryKind {
+    File,
+    Directory,
+}
+
+/// An entry of a directory, as returned by `FileSystem::read_dir`.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct DirEntry {
+    pub path: OwnedPath,
+    pub name: String,
+    pub kind: EntryKind,
+}
+
+impl DirEntry {
+    /// Create the entry of a directory's child.
+    pub fn new(parent: &Path, name: &str, kind: EntryKind) -> Self {
+        let mut path = components(parent);
+        path.push(name.to_owned());
+
+        Self {
+            path: to_path(&path),
+            name: name.to_owned(),
+            kind,
+        }
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.kind == EntryKind::File
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.kind == EntryKind::Directory
+    }
+}
+
+type Filter<'a> = Box<dyn FnMut(&DirEntry) -> bool + 'a>;
+
+/// A recursive iterator over the entries under a directory, created by `FileSystem::walk`.
+/// Entries are visited depth-first, each directory before its children, and sorted by name.
+///
+/// Errors reading a directory are returned in place of its children, and the walk
+/// continues with the next entries.
+pub struct Walk<'a, Fs: FileSystem> {
+    fs: &'a Fs,
+    /// The next directory to read, with the depth of its children.
+    next_dir: Option<(OwnedPath, usize)>,
+    /// The entries left to visit, in reverse order, with their depth.
+    stack: Vec<(DirEntry, usize)>,
+    max_depth: Option<usize>,
+    filter: Option<Filter<'a>>,
+}
+
+impl<'a, Fs: FileSystem> Walk<'a, Fs> {
+    pub fn new<P: AsRef<Path>>(fs: &'a Fs, path: P) -> Self {
+        Self {
+            fs,
+            next_dir: Some((path.as_ref().to_owned(), 1)),
+            stack: Vec::new(),
+            max_depth: None,
+            filter: None,
+        }
+    }
+
+    /// Only visit entries up to this depth; the children of the directory walked have a
+    /// depth of 1.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Skip the entries for which the predicate returns false. The children of a skipped
+    /// directory are skipped as well.
+    pub fn filter_entry<F: FnMut(&DirEntry) -> bool + 'a>(mut self, predicate: F) -> Self {
+        self.filter = Some(Box::new(predicate));
+        self
+    }
+}
+
+impl<'a, Fs: FileSystem> Iterator for Walk<'a, Fs> {
+    type Item = Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((dir, depth)) = self.next_dir.take() {
+            if self.max_depth.is_none_or(|max| depth <= max) {
+                match self.fs.read_dir(&dir) {
+                    Ok(entries) => self
+                        .stack
+                        .extend(entries.into_iter().rev().map(|entry| (entry, depth))),
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+        }
+
+        loop {
+            let (entry, depth) = self.stack.pop()?;
+            if let Some(ref mut filter) = self.filter {
+                if !filter(&entry) {
+                    continue;
+                }
+            }
+
+            if entry.is_dir() {
+                self.next_dir = Some((entry.path.clone(), depth + 1));
+            }
+            return Some(Ok(entry));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::MemoryFileSystem;
+
+    fn fs() -> Result<MemoryFileSystem> {
+        let mut fs = MemoryFileSystem::default();
+        fs.write("/b/c.txt", "c")?;
+        fs.write("/b/d/e.txt", "e")?;
+        fs.write("/a.txt", "a")?;
+        fs.write("/f/g.txt", "g")?;
+        Ok(fs)
+    }
+
+    fn paths<'a>(walk: Walk<'a, MemoryFileSystem>) -> Result<Vec<String>> {
+        walk.map(|entry| entry.map(|entry| AsRef::<str>::as_ref(entry.path.as_path()).to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn order() -> Result<()> {
+        let fs = fs()?;
+        assert_eq!(
+            paths(fs.walk("/"))?,
+            [
+                "/a.txt",
+                "/b",
+                "/b/c.txt",
+                "/b/d",
+                "/b/d/e.txt",
+                "/f",
+                "/f/g.txt"
+            ]
+        );
+        assert_eq!(paths(fs.walk("/b/d"))?, ["/b/d/e.txt"]);
+        Ok(())
+    }
+
+    #[test]
+    fn max_depth() -> Result<()> {
+        let fs = fs()?;
+        assert_eq!(paths(fs.walk("/").max_depth(1))?, ["/a.txt", "/b", "/f"]);
+        assert_eq!(
+            paths(fs.walk("/b").max_depth(2))?,
+            ["/b/c.txt", "/b/d", "/b/d/e.txt"]
+        );
+        assert_eq!(paths(fs.walk("/").max_depth(0))?, Vec::<String>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn filter_entry() -> Result<()> {
+        let fs = fs()?;
+        let walk = fs.walk("/").filter_entry(|entry| entry.name != "b");
+        assert_eq!(paths(walk)?, ["/a.txt", "/f", "/f/g.txt"]);
+
+        let walk = fs.walk("/").filter_entry(DirEntry::is_dir);
+        assert_eq!(paths(walk)?, ["/b", "/b/d", "/f"]);
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let fs = MemoryFileSystem::default();
+        let mut walk = fs.walk("/missing");
+        assert!(walk.next().unwrap().is_err());
+        assert!(walk.next().is_none());
+    }
+}
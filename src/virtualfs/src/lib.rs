@@ -1,8 +1,15 @@
+#![no_std]
+
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
 mod error;
 pub mod fs;
 mod path;
 
-pub use error::Error;
+pub use error::{Error, IoError};
+pub use path::{Component, OwnedPath, Path};
 
 #[cfg(test)]
 mod tests {
@@ -1,5 +1,8 @@
-use std::borrow::Borrow;
-use std::ops::Deref;
+use alloc::borrow::{Borrow, ToOwned};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Deref;
 
 pub const SEPARATOR: char = '/';
 
@@ -31,13 +34,13 @@ impl<'a> Component<'a> {
     }
 }
 
-impl<'a> Into<&'a str> for Component<'a> {
-    fn into(self) -> &'a str {
-        match self {
-            Self::RootDir => "",
-            Self::CurDir => ".",
-            Self::ParentDir => "..",
-            Self::Named(x) => x,
+impl<'a> From<Component<'a>> for &'a str {
+    fn from(component: Component<'a>) -> &'a str {
+        match component {
+            Component::RootDir => "",
+            Component::CurDir => ".",
+            Component::ParentDir => "..",
+            Component::Named(x) => x,
         }
     }
 }
@@ -59,19 +62,19 @@ impl<'a> AsRef<Path> for Component<'a> {
     }
 }
 
-impl<'a> std::fmt::Debug for Component<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<'a> core::fmt::Debug for Component<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_fmt(format_args!(r#"Component("{}")"#, self.as_ref() as &str))
     }
 }
 
-impl<'a> std::fmt::Display for Component<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<'a> core::fmt::Display for Component<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_str(self.as_ref())
     }
 }
 
-impl<'a, A: AsRef<str>> std::cmp::PartialEq<A> for Component<'a> {
+impl<'a, A: AsRef<str>> core::cmp::PartialEq<A> for Component<'a> {
     fn eq(&self, other: &A) -> bool {
         (self.as_ref() as &str) == other.as_ref()
     }
@@ -114,16 +117,16 @@ impl OwnedPath {
     }
 }
 
-impl std::fmt::Debug for OwnedPath {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Debug for OwnedPath {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("OwnedPath")
             .field("inner", &self.inner)
             .finish()
     }
 }
 
-impl std::fmt::Display for OwnedPath {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for OwnedPath {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         for c in self.iter() {
             f.write_str(c.as_ref())?;
             f.write_str(&SEPARATOR.to_string())?;
@@ -187,7 +190,7 @@ impl Path {
         &self.inner == "/"
     }
 
-    pub fn basename(&self) -> Option<Component> {
+    pub fn basename(&self) -> Option<Component<'_>> {
         if !self.is_entry() {
             None
         } else {
@@ -195,7 +198,7 @@ impl Path {
         }
     }
 
-    pub fn dirname(&self) -> Option<Component> {
+    pub fn dirname(&self) -> Option<Component<'_>> {
         if self.is_empty() || self.is_root() {
             return None;
         }
@@ -205,17 +208,11 @@ impl Path {
             self.inner.rfind(is_separator)
         };
 
-        if last_slash.is_none() {
-            return None;
-        }
-        let previous_slash = self.inner[0..last_slash.unwrap()].rfind(is_separator);
-        if previous_slash.is_none() {
-            None
-        } else {
-            Some(Component::from_unchecked_name(
-                &self.inner[previous_slash.unwrap()..last_slash.unwrap()],
-            ))
-        }
+        let last_slash = last_slash?;
+        let previous_slash = self.inner[0..last_slash].rfind(is_separator)?;
+        Some(Component::from_unchecked_name(
+            &self.inner[previous_slash..last_slash],
+        ))
     }
 
     pub fn parent(&self) -> Option<&Path> {
@@ -248,6 +245,11 @@ impl Path {
         )
     }
 
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.inner
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.inner.len()
@@ -268,6 +270,12 @@ impl ToOwned for Path {
     }
 }
 
+impl AsRef<Path> for Path {
+    fn as_ref(&self) -> &Path {
+        self
+    }
+}
+
 impl AsRef<Path> for str {
     fn as_ref(&self) -> &Path {
         Path::new(self)
@@ -280,18 +288,44 @@ impl AsRef<str> for Path {
     }
 }
 
-impl<A: AsRef<str>> std::cmp::PartialEq<A> for Path {
+impl<A: AsRef<str>> core::cmp::PartialEq<A> for Path {
     fn eq(&self, other: &A) -> bool {
         (self.as_ref() as &str) == other.as_ref()
     }
 }
 
-impl std::cmp::PartialEq<str> for &Path {
+impl core::cmp::PartialEq<str> for &Path {
     fn eq(&self, other: &str) -> bool {
         (self.as_ref() as &str) == other
     }
 }
 
+/// The components of a path, with `.` and `..` resolved. Absolute paths start with an
+/// empty component.
+pub(crate) fn components(path: &Path) -> Vec<String> {
+    let mut components = Vec::new();
+    for component in path.iter() {
+        match component {
+            Component::RootDir => components.push(String::new()),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if components.last().is_some_and(|c: &String| !c.is_empty()) {
+                    components.pop();
+                }
+            }
+            Component::Named(name) => components.push(name.to_owned()),
+        }
+    }
+    components
+}
+
+pub(crate) fn to_path(components: &[String]) -> OwnedPath {
+    match components.split_first() {
+        Some((first, rest)) if first.is_empty() => OwnedPath::from(format!("/{}", rest.join("/"))),
+        _ => OwnedPath::from(components.join("/")),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;